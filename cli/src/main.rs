@@ -1,11 +1,39 @@
 use clap::Clap;
-use escpos_md::{MarkdownParser, PrinterConfig, Result};
+use escpos_md::preview::TextPreview;
+use escpos_md::{
+    MarkdownParser, MarkdownRenderOptions, Printer, PrinterConfig, PrinterDevice, Result,
+    Strictness,
+};
 use std::io::{self, Read};
 
 #[derive(Clap)]
 struct Opts {
     #[clap(short, long)]
     no_cut: bool,
+    /// Print a text preview of the receipt instead of the ESC/POS bytes
+    #[clap(short, long)]
+    preview: bool,
+    /// Skip unsupported Markdown with a warning instead of failing
+    #[clap(short, long)]
+    lenient: bool,
+}
+
+fn print<D: PrinterDevice>(printer: &mut Printer<D>, md: &str, opts: &Opts) -> Result<()> {
+    let parser = MarkdownParser::new(md);
+    let mut render_opts = MarkdownRenderOptions::default();
+    if opts.lenient {
+        render_opts.strictness = Strictness::Lenient;
+    }
+    let warnings = printer
+        .reset()?
+        .markdown_with_offsets(parser.into_offset_iter(), &render_opts)?;
+    for warning in warnings {
+        eprintln!("warning: {}", warning);
+    }
+    if !opts.no_cut {
+        printer.cut()?;
+    }
+    Ok(())
 }
 
 fn main() -> Result<()> {
@@ -13,13 +41,15 @@ fn main() -> Result<()> {
 
     let mut md = String::new();
     io::stdin().read_to_string(&mut md)?;
-    let parser = MarkdownParser::new(&md);
 
-    let mut printer = PrinterConfig::tm_t20ii().build(io::stdout())?;
-
-    printer.reset()?.markdown(parser, &Default::default())?;
-    if !opts.no_cut {
-        printer.cut()?;
+    let config = PrinterConfig::tm_t20ii();
+    if opts.preview {
+        let mut printer = config.build(TextPreview::new(&config))?;
+        print(&mut printer, &md, &opts)?;
+        print!("{}", printer.device().render_ansi());
+    } else {
+        let mut printer = config.build(io::stdout())?;
+        print(&mut printer, &md, &opts)?;
     }
 
     Ok(())
@@ -0,0 +1,23 @@
+use escpos_md::command::{HriPosition, Justification};
+use escpos_md::instruction::{Barcode, BarcodeSystem, Code128Set};
+use escpos_md::{PrinterConfig, Result};
+use std::io;
+
+fn main() -> Result<()> {
+    PrinterConfig::tm_t20ii()
+        .build(io::stdout())?
+        .reset()?
+        .justification(Justification::Center)?
+        .barcode_hri(HriPosition::Below)?
+        .barcode_height(80)?
+        .barcode_width(3)?
+        .barcode(&Barcode::new(BarcodeSystem::Ean13, "4006381333931")?)?
+        .feed_lines(1)?
+        .barcode(&Barcode::new(
+            BarcodeSystem::Code128(Code128Set::B),
+            "Order #1234",
+        )?)?
+        .feed_lines(5)?
+        .cut()?;
+    Ok(())
+}
@@ -12,7 +12,14 @@ fn main() -> Result<()> {
         .println("threshold:")?
         .image(&EscposImage::new(
             &img,
-            &ImageOptions::default().bit_map_algorithm(BitMapAlgorithm::Threshold(80)),
+            ImageOptions::default().bit_map_algorithm(BitMapAlgorithm::Threshold(80)),
+        ))?
+        .println("atkinson:")?
+        .image(&EscposImage::new(
+            &img,
+            ImageOptions::default()
+                .bit_map_algorithm(BitMapAlgorithm::Atkinson)
+                .contrast(1.2)?,
         ))?
         .cut()?;
     Ok(())
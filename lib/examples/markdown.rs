@@ -55,14 +55,35 @@ And then there's also some block quotes
 > Like this block quote example
 > With numerous lines
 
+A table with aligned columns
+
+| Item          | Qty | Price |
+|:--------------|:---:|------:|
+| Coffee        |  2  |  6.00 |
+| Blueberry muffin with extra long description | 1 | 3.50 |
+
+Code blocks can also be printed as barcodes and QR codes
+
+```barcode ean13
+4006381333931
+```
+
+```qr
+https://github.com/jpopesculian/escpos-md
+```
+
 And of course an image
 
 ![lena](./examples/lena.jpg "With explanation")
+
+```drawer
+```
 "#;
 
 fn main() -> Result<()> {
     let mut options = MarkdownParserOptions::empty();
     options.insert(MarkdownParserOptions::ENABLE_STRIKETHROUGH);
+    options.insert(MarkdownParserOptions::ENABLE_TABLES);
     let parser = MarkdownParser::new_ext(TEST_MD, options);
     PrinterConfig::tm_t20ii()
         .build(io::stdout())?
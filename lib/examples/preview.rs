@@ -0,0 +1,29 @@
+use escpos_md::preview::RasterPreview;
+use escpos_md::{MarkdownParser, MarkdownParserOptions, PrinterConfig, Result};
+
+const TEST_MD: &str = r#"
+# Receipt
+
+| Item   | Qty | Price |
+|:-------|:---:|------:|
+| Coffee |  2  |  6.00 |
+| Muffin |  1  |  3.50 |
+
+Thank you for your __visit__!
+
+![lena](./examples/lena.jpg "Come again")
+"#;
+
+fn main() -> Result<()> {
+    let config = PrinterConfig::tm_t20ii();
+    let mut printer = config.build(RasterPreview::new(&config))?;
+    printer
+        .reset()?
+        .markdown(
+            MarkdownParser::new_ext(TEST_MD, MarkdownParserOptions::ENABLE_TABLES),
+            &Default::default(),
+        )?
+        .cut()?;
+    printer.device().render().save("preview.png")?;
+    Ok(())
+}
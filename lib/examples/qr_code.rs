@@ -0,0 +1,22 @@
+use escpos_md::command::{Justification, QrErrorCorrection};
+use escpos_md::instruction::{QrCode, QrCodeOptions};
+use escpos_md::{PrinterConfig, Result};
+use std::io;
+
+fn main() -> Result<()> {
+    let qr_code = QrCode::new(
+        "https://github.com/jpopesculian/escpos-md",
+        QrCodeOptions::default()
+            .module_size(8)?
+            .error_correction(QrErrorCorrection::Q),
+    )?;
+    PrinterConfig::tm_t20ii()
+        .build(io::stdout())?
+        .reset()?
+        .justification(Justification::Center)?
+        .println("Track your order")?
+        .qr_code(&qr_code)?
+        .feed_lines(5)?
+        .cut()?;
+    Ok(())
+}
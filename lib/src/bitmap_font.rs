@@ -0,0 +1,86 @@
+//! Bitmap fonts used to draw text into images. The glyphs are the public
+//! domain X11 "misc-fixed" fonts covering ISO 8859-1, stored as 1 bit per
+//! pixel images with 16 glyphs per row. With the `bitmap-fallback` feature
+//! the 10x20 font covering most of the Basic Multilingual Plane of
+//! ISO 10646 is bundled too.
+
+use crate::command::Font;
+
+/// Characters of the ISO 8859-1 fonts, the printable ones
+const ISO_8859_1_RANGES: &[u8] = &[0x00, 0x20, 0x00, 0x7e, 0x00, 0xa0, 0x00, 0xff];
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct BitmapFont {
+    data: &'static [u8],
+    /// Characters in the order of their glyphs, as ranges of the first and
+    /// the last code point in big-endian 16 bit integers
+    ranges: &'static [u8],
+    image_width: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+pub(crate) const FONT_10X20: BitmapFont = BitmapFont {
+    data: include_bytes!("../assets/fonts/misc_fixed_10x20_iso_8859_1.raw"),
+    ranges: ISO_8859_1_RANGES,
+    image_width: 160,
+    width: 10,
+    height: 20,
+};
+
+pub(crate) const FONT_9X18: BitmapFont = BitmapFont {
+    data: include_bytes!("../assets/fonts/misc_fixed_9x18_iso_8859_1.raw"),
+    ranges: ISO_8859_1_RANGES,
+    image_width: 144,
+    width: 9,
+    height: 18,
+};
+
+#[cfg(feature = "bitmap-fallback")]
+pub(crate) const FONT_10X20_ISO_10646: BitmapFont = BitmapFont {
+    data: include_bytes!("../assets/fonts/misc_fixed_10x20_iso_10646.raw"),
+    ranges: include_bytes!("../assets/fonts/misc_fixed_10x20_iso_10646.ranges"),
+    image_width: 160,
+    width: 10,
+    height: 20,
+};
+
+impl BitmapFont {
+    /// Closest bitmap font to a printer font
+    pub fn for_font(font: Font) -> Self {
+        match font {
+            Font::FontA => FONT_10X20,
+            _ => FONT_9X18,
+        }
+    }
+
+    fn glyph_index(&self, ch: char) -> Option<usize> {
+        let code = ch as u32;
+        let mut offset = 0;
+        for range in self.ranges.chunks_exact(4) {
+            let first = u16::from_be_bytes([range[0], range[1]]) as u32;
+            let last = u16::from_be_bytes([range[2], range[3]]) as u32;
+            if (first..=last).contains(&code) {
+                return Some(offset + (code - first) as usize);
+            }
+            offset += (last - first) as usize + 1;
+        }
+        None
+    }
+
+    pub fn has_glyph(&self, ch: char) -> bool {
+        self.glyph_index(ch).is_some()
+    }
+
+    /// Returns whether the pixel of the glyph is set, `false` for missing glyphs
+    pub fn pixel(&self, ch: char, x: usize, y: usize) -> bool {
+        let idx = match self.glyph_index(ch) {
+            Some(idx) if x < self.width && y < self.height => idx,
+            _ => return false,
+        };
+        let px = (idx % 16) * self.width + x;
+        let py = (idx / 16) * self.height + y;
+        let byte = self.data[py * self.image_width / 8 + px / 8];
+        byte & (0x80 >> (px % 8)) != 0
+    }
+}
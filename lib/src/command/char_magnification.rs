@@ -33,7 +33,7 @@ impl CharMagnification {
 
     pub fn check_param(param: u8) -> Result<()> {
         // 0 < param <= 8
-        if param < PARAM_MIN || param > PARAM_MAX {
+        if !(PARAM_MIN..=PARAM_MAX).contains(&param) {
             Err(Error::InvalidCharMagnification)
         } else {
             Ok(())
@@ -41,12 +41,16 @@ impl CharMagnification {
     }
 
     pub fn clamp_param(param: u8) -> u8 {
-        param.max(PARAM_MIN).min(PARAM_MAX)
+        param.clamp(PARAM_MIN, PARAM_MAX)
     }
 
     pub fn to_byte(&self) -> u8 {
         (self.height - 1) | ((self.width - 1) << 4)
     }
+
+    pub fn from_byte(byte: u8) -> Result<Self> {
+        Self::new((byte >> 4) + 1, (byte & 0x0f) + 1)
+    }
 }
 
 impl Default for CharMagnification {
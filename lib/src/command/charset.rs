@@ -63,4 +63,29 @@ impl Charset {
             Charset::Arabia => vec![0x11],
         }
     }
+
+    /// Parses the parameter of the esc/pos command
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        Some(match byte {
+            0x00 => Charset::USA,
+            0x01 => Charset::France,
+            0x02 => Charset::Germany,
+            0x03 => Charset::UK,
+            0x04 => Charset::Denmark1,
+            0x05 => Charset::Sweden,
+            0x06 => Charset::Italy,
+            0x07 => Charset::Spain1,
+            0x08 => Charset::Japan,
+            0x09 => Charset::Norway,
+            0x0a => Charset::Denmark2,
+            0x0b => Charset::Spain2,
+            0x0c => Charset::LatinAmerica,
+            0x0d => Charset::Korea,
+            0x0e => Charset::SloveniaCroatia,
+            0x0f => Charset::China,
+            0x10 => Charset::Vietnam,
+            0x11 => Charset::Arabia,
+            _ => return None,
+        })
+    }
 }
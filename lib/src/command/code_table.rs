@@ -1,16 +1,157 @@
-/// Possible character sets
-#[derive(Clone, Debug, Copy, PartialEq, Eq)]
+/// Character code tables selected with ESC t
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Hash, Default)]
 pub enum CodeTable {
-    USA,
-    Latin2,
+    /// USA, standard Europe
+    #[default]
+    Pc437,
+    /// Half-width katakana
+    Katakana,
+    /// Multilingual
+    Pc850,
+    /// Portuguese
+    Pc860,
+    /// Canadian-French
+    Pc863,
+    /// Nordic
+    Pc865,
+    /// Turkish
+    Pc857,
+    /// Greek
+    Pc737,
+    /// Greek
+    Iso8859_7,
+    /// Western European
+    Wpc1252,
+    /// Cyrillic #2
+    Pc866,
+    /// Latin 2
+    Pc852,
+    /// Euro
+    Pc858,
+    /// Arabic
+    Pc720,
+    /// Baltic Rim
+    Pc775,
+    /// Cyrillic
+    Pc855,
+    /// Icelandic
+    Pc861,
+    /// Hebrew
+    Pc862,
+    /// Arabic
+    Pc864,
+    /// Greek
+    Pc869,
+    /// Latin 2
+    Iso8859_2,
+    /// Latin 9
+    Iso8859_15,
+    /// Central European
+    Wpc1250,
+    /// Cyrillic
+    Wpc1251,
+    /// Greek
+    Wpc1253,
+    /// Turkish
+    Wpc1254,
+    /// Hebrew
+    Wpc1255,
+    /// Arabic
+    Wpc1256,
+    /// Baltic Rim
+    Wpc1257,
+    /// Vietnamese
+    Wpc1258,
 }
 
 impl CodeTable {
-    /// Returns the byte representation of the esc/pos command
-    pub fn as_bytes(&self) -> Vec<u8> {
+    pub const ALL: [CodeTable; 30] = [
+        Self::Pc437,
+        Self::Katakana,
+        Self::Pc850,
+        Self::Pc860,
+        Self::Pc863,
+        Self::Pc865,
+        Self::Pc857,
+        Self::Pc737,
+        Self::Iso8859_7,
+        Self::Wpc1252,
+        Self::Pc866,
+        Self::Pc852,
+        Self::Pc858,
+        Self::Pc720,
+        Self::Pc775,
+        Self::Pc855,
+        Self::Pc861,
+        Self::Pc862,
+        Self::Pc864,
+        Self::Pc869,
+        Self::Iso8859_2,
+        Self::Iso8859_15,
+        Self::Wpc1250,
+        Self::Wpc1251,
+        Self::Wpc1253,
+        Self::Wpc1254,
+        Self::Wpc1255,
+        Self::Wpc1256,
+        Self::Wpc1257,
+        Self::Wpc1258,
+    ];
+
+    #[deprecated(note = "renamed to `Pc437`")]
+    #[allow(non_upper_case_globals)]
+    pub const USA: CodeTable = Self::Pc437;
+    /// Selects table 2 as before, which is PC850, not Latin 2
+    #[deprecated(note = "use `Pc850` for the same table or `Pc852` for Latin 2")]
+    #[allow(non_upper_case_globals)]
+    pub const Latin2: CodeTable = Self::Pc850;
+
+    /// The `n` parameter of ESC t
+    pub fn as_byte(&self) -> u8 {
         match self {
-            CodeTable::USA => vec![0x00],
-            CodeTable::Latin2 => vec![0x02],
+            Self::Pc437 => 0,
+            Self::Katakana => 1,
+            Self::Pc850 => 2,
+            Self::Pc860 => 3,
+            Self::Pc863 => 4,
+            Self::Pc865 => 5,
+            Self::Pc857 => 13,
+            Self::Pc737 => 14,
+            Self::Iso8859_7 => 15,
+            Self::Wpc1252 => 16,
+            Self::Pc866 => 17,
+            Self::Pc852 => 18,
+            Self::Pc858 => 19,
+            Self::Pc720 => 32,
+            Self::Pc775 => 33,
+            Self::Pc855 => 34,
+            Self::Pc861 => 35,
+            Self::Pc862 => 36,
+            Self::Pc864 => 37,
+            Self::Pc869 => 38,
+            Self::Iso8859_2 => 39,
+            Self::Iso8859_15 => 40,
+            Self::Wpc1250 => 45,
+            Self::Wpc1251 => 46,
+            Self::Wpc1253 => 47,
+            Self::Wpc1254 => 48,
+            Self::Wpc1255 => 49,
+            Self::Wpc1256 => 50,
+            Self::Wpc1257 => 51,
+            Self::Wpc1258 => 52,
         }
     }
+
+    /// Returns the byte representation of the esc/pos command
+    pub fn as_bytes(&self) -> Vec<u8> {
+        vec![self.as_byte()]
+    }
+
+    /// Parses the parameter of the esc/pos command
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .find(|table| table.as_byte() == byte)
+            .copied()
+    }
 }
@@ -0,0 +1,50 @@
+/// Whether the paper is cut through or a point is left uncut
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub enum CutType {
+    #[default]
+    Full,
+    Partial,
+}
+
+/// How the paper is fed before cutting, the functions of GS V
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum CutFunction {
+    /// Cuts at the current position (function A)
+    Cut,
+    /// Feeds `n` units and cuts (function B)
+    FeedAndCut(u8),
+    /// Sets the cut position `n` units ahead, the paper is cut once it
+    /// reaches it (function C)
+    SetCutPosition(u8),
+    /// Feeds to the cut position plus `n` units and cuts (function D)
+    FeedToCutPosition(u8),
+}
+
+impl CutFunction {
+    /// Returns the `m` and `n` parameters of GS V
+    pub fn to_bytes(&self, cut_type: CutType) -> (u8, Option<u8>) {
+        let partial = matches!(cut_type, CutType::Partial) as u8;
+        match self {
+            Self::Cut => (partial, None),
+            Self::FeedAndCut(n) => (65 + partial, Some(*n)),
+            Self::SetCutPosition(n) => (97 + partial, Some(*n)),
+            Self::FeedToCutPosition(n) => (103 + partial, Some(*n)),
+        }
+    }
+
+    /// Builds the function from the `m` and `n` parameters of GS V
+    pub fn from_bytes(m: u8, n: Option<u8>) -> Option<(CutType, Self)> {
+        use CutType::*;
+        Some(match (m, n) {
+            (0 | 48, _) => (Full, Self::Cut),
+            (1 | 49, _) => (Partial, Self::Cut),
+            (65, Some(n)) => (Full, Self::FeedAndCut(n)),
+            (66, Some(n)) => (Partial, Self::FeedAndCut(n)),
+            (97, Some(n)) => (Full, Self::SetCutPosition(n)),
+            (98, Some(n)) => (Partial, Self::SetCutPosition(n)),
+            (103, Some(n)) => (Full, Self::FeedToCutPosition(n)),
+            (104, Some(n)) => (Partial, Self::FeedToCutPosition(n)),
+            _ => return None,
+        })
+    }
+}
@@ -1,7 +1,8 @@
 /// Common fonts used in thermal printers
-#[derive(Clone, Debug, Copy, Eq, PartialEq)]
+#[derive(Clone, Debug, Copy, Eq, PartialEq, Default)]
 #[repr(u8)]
 pub enum Font {
+    #[default]
     FontA,
     FontB,
     FontC,
@@ -9,12 +10,6 @@ pub enum Font {
     FontE,
 }
 
-impl Default for Font {
-    fn default() -> Self {
-        Font::FontA
-    }
-}
-
 impl Font {
     /// Byte representation of each font.
     pub fn as_bytes(&self) -> Vec<u8> {
@@ -26,4 +21,16 @@ impl Font {
             Font::FontE => vec![0x04],
         }
     }
+
+    /// Parses the parameter of the esc/pos command
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0x00 | 0x30 => Some(Font::FontA),
+            0x01 | 0x31 => Some(Font::FontB),
+            0x02 | 0x32 => Some(Font::FontC),
+            0x03 | 0x33 => Some(Font::FontD),
+            0x04 | 0x34 => Some(Font::FontE),
+            _ => None,
+        }
+    }
 }
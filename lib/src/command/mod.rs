@@ -1,46 +1,162 @@
 mod char_magnification;
 mod charset;
 mod code_table;
+mod cut;
 mod font;
+mod qr_code;
 
 pub use char_magnification::CharMagnification;
 pub use charset::Charset;
 pub use code_table::CodeTable;
+pub use cut::{CutFunction, CutType};
 pub use font::Font;
+pub use qr_code::{QrErrorCorrection, QrModel};
 
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
 #[repr(u8)]
 pub enum UnderlineThickness {
+    #[default]
     Off = 0,
     OneDot = 1,
     TwoDot = 2,
 }
 
-impl Default for UnderlineThickness {
-    fn default() -> Self {
-        Self::Off
-    }
-}
-
-#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
 #[repr(u8)]
 pub enum Justification {
+    #[default]
     Left = 0,
     Center = 1,
     Right = 2,
 }
 
-impl Default for Justification {
-    fn default() -> Self {
-        Self::Left
+/// Position of the human readable interpretation (HRI) of barcodes
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+#[repr(u8)]
+pub enum HriPosition {
+    #[default]
+    None = 0,
+    Above = 1,
+    Below = 2,
+    Both = 3,
+}
+
+/// Pin of the drawer kick-out connector to pulse
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+#[repr(u8)]
+pub enum DrawerPin {
+    #[default]
+    Pin2 = 0,
+    Pin5 = 1,
+}
+
+/// Status requested with DLE EOT
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+#[repr(u8)]
+pub enum StatusRequest {
+    Printer = 1,
+    Offline = 2,
+    Error = 3,
+    Paper = 4,
+}
+
+/// Status changes reported by Automatic Status Back
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Default)]
+pub struct StatusBackMask {
+    pub drawer: bool,
+    pub online: bool,
+    pub error: bool,
+    pub paper: bool,
+}
+
+impl StatusBackMask {
+    pub fn all() -> Self {
+        Self {
+            drawer: true,
+            online: true,
+            error: true,
+            paper: true,
+        }
+    }
+
+    pub fn from_byte(byte: u8) -> Self {
+        Self {
+            drawer: byte & 0x01 != 0,
+            online: byte & 0x02 != 0,
+            error: byte & 0x04 != 0,
+            paper: byte & 0x08 != 0,
+        }
+    }
+
+    pub fn to_byte(&self) -> u8 {
+        self.drawer as u8
+            | (self.online as u8) << 1
+            | (self.error as u8) << 2
+            | (self.paper as u8) << 3
+    }
+}
+
+impl UnderlineThickness {
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 | 48 => Some(Self::Off),
+            1 | 49 => Some(Self::OneDot),
+            2 | 50 => Some(Self::TwoDot),
+            _ => None,
+        }
+    }
+}
+
+impl DrawerPin {
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 | 48 => Some(Self::Pin2),
+            1 | 49 => Some(Self::Pin5),
+            _ => None,
+        }
+    }
+}
+
+impl StatusRequest {
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            1 => Some(Self::Printer),
+            2 => Some(Self::Offline),
+            3 => Some(Self::Error),
+            4 => Some(Self::Paper),
+            _ => None,
+        }
+    }
+}
+
+impl Justification {
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 | 48 => Some(Self::Left),
+            1 | 49 => Some(Self::Center),
+            2 | 50 => Some(Self::Right),
+            _ => None,
+        }
+    }
+}
+
+impl HriPosition {
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 | 48 => Some(Self::None),
+            1 | 49 => Some(Self::Above),
+            2 | 50 => Some(Self::Below),
+            3 | 51 => Some(Self::Both),
+            _ => None,
+        }
     }
 }
 
 /// Common commands usefull for the printer
-#[derive(Clone, Debug, Copy)]
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
 pub enum Command {
-    /// Cuts the paper after 0x96 vertical spaces
-    Cut,
+    /// Cuts the paper. Equivalent to GS V
+    Cut(CutType, CutFunction),
     /// Equivalent to ESC @
     Init,
     /// Print mode selected to init the fonts. Equivalent to ESC ! 0
@@ -67,14 +183,58 @@ pub enum Command {
     SplitWords(bool),
     LeftMargin(u16),
     Justification(Justification),
+    /// Sets the HRI position of barcodes. Equivalent to GS H
+    BarcodeHri(HriPosition),
+    /// Sets the HRI font of barcodes. Equivalent to GS f
+    BarcodeHriFont(Font),
+    /// Sets the module width of barcodes (2 to 6). Equivalent to GS w
+    BarcodeWidth(u8),
+    /// Sets the height of barcodes in dots. Equivalent to GS h
+    BarcodeHeight(u8),
+    /// Selects the QR code model. Equivalent to GS ( k fn 165
+    QrModel(QrModel),
+    /// Sets the QR code module size in dots (1 to 16). Equivalent to GS ( k fn 167
+    QrModuleSize(u8),
+    /// Sets the QR code error correction level. Equivalent to GS ( k fn 169
+    QrErrorCorrection(QrErrorCorrection),
+    /// Prints the QR code stored in the symbol storage area. Equivalent to GS ( k fn 181
+    QrPrint,
+    /// Requests a status byte from the printer in real time. Equivalent to DLE EOT
+    TransmitStatus(StatusRequest),
+    /// Enables or disables Automatic Status Back. Equivalent to GS a
+    AutoStatusBack(StatusBackMask),
+    /// Pulses a drawer kick-out pin, on for `t1` and off for `t2` times 2 ms.
+    /// Equivalent to ESC p
+    DrawerKick(DrawerPin, u8, u8),
+    /// Pulses a drawer kick-out pin in real time for `t` (1 to 8) times
+    /// 100 ms. Equivalent to DLE DC4 1
+    DrawerPulse(DrawerPin, u8),
+    /// Beeps `n` times for `t` times 50 ms, both 1 to 9. Equivalent to ESC B
+    /// on printers with a buzzer
+    Beep(u8, u8),
+    /// Enters or leaves double-byte character mode. Equivalent to FS & and
+    /// FS .
+    DoubleByteMode(bool),
+    /// Sets the left and right spacing of double-byte characters in dots.
+    /// Equivalent to FS S
+    DoubleByteSpacing(u8, u8),
+    /// Turns quadruple-size double-byte characters on or off. Equivalent to
+    /// FS W
+    DoubleByteQuadruple(bool),
 }
 
 impl Command {
     /// Returns the byte-array representation of each command
     pub fn as_bytes(&self) -> Vec<u8> {
         match self {
-            Command::Cut => vec![0x1d, 0x56, 0x41, 0x96],
-            Command::Init => vec![0x1d, 0x40],
+            Command::Cut(cut_type, function) => {
+                let mut res = vec![0x1d, 0x56];
+                let (m, n) = function.to_bytes(*cut_type);
+                res.push(m);
+                res.extend(n);
+                res
+            }
+            Command::Init => vec![0x1b, 0x40],
             Command::PrintModeDefault => vec![0x01b, 0x21, 0x00],
             Command::Charset(charset) => {
                 let mut res = vec![0x1b, 0x52];
@@ -109,6 +269,31 @@ impl Command {
                 res
             }
             Command::Justification(justification) => vec![0x1b, 0x61, *justification as u8],
+            Command::BarcodeHri(position) => vec![0x1d, 0x48, *position as u8],
+            Command::BarcodeHriFont(font) => {
+                let mut res = vec![0x1d, 0x66];
+                res.append(&mut font.as_bytes());
+                res
+            }
+            Command::BarcodeWidth(width) => vec![0x1d, 0x77, *width],
+            Command::BarcodeHeight(height) => vec![0x1d, 0x68, *height],
+            Command::QrModel(model) => {
+                vec![0x1d, 0x28, 0x6b, 0x04, 0x00, 0x31, 0x41, *model as u8, 0x00]
+            }
+            Command::QrModuleSize(size) => vec![0x1d, 0x28, 0x6b, 0x03, 0x00, 0x31, 0x43, *size],
+            Command::QrErrorCorrection(level) => {
+                vec![0x1d, 0x28, 0x6b, 0x03, 0x00, 0x31, 0x45, *level as u8]
+            }
+            Command::QrPrint => vec![0x1d, 0x28, 0x6b, 0x03, 0x00, 0x31, 0x51, 0x30],
+            Command::TransmitStatus(request) => vec![0x10, 0x04, *request as u8],
+            Command::AutoStatusBack(mask) => vec![0x1d, 0x61, mask.to_byte()],
+            Command::DrawerKick(pin, on, off) => vec![0x1b, 0x70, *pin as u8, *on, *off],
+            Command::DrawerPulse(pin, time) => vec![0x10, 0x14, 0x01, *pin as u8, *time],
+            Command::Beep(times, duration) => vec![0x1b, 0x42, *times, *duration],
+            Command::DoubleByteMode(true) => vec![0x1c, 0x26],
+            Command::DoubleByteMode(false) => vec![0x1c, 0x2e],
+            Command::DoubleByteSpacing(left, right) => vec![0x1c, 0x53, *left, *right],
+            Command::DoubleByteQuadruple(enabled) => vec![0x1c, 0x57, *enabled as u8],
         }
     }
 }
@@ -0,0 +1,43 @@
+/// QR code symbol models
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum QrModel {
+    Model1 = 0x31,
+    Model2 = 0x32,
+}
+
+/// QR code error correction levels
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
+#[repr(u8)]
+pub enum QrErrorCorrection {
+    /// Recovers 7% of the data
+    L = 0x30,
+    /// Recovers 15% of the data
+    M = 0x31,
+    /// Recovers 25% of the data
+    Q = 0x32,
+    /// Recovers 30% of the data
+    H = 0x33,
+}
+
+impl QrModel {
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0x31 => Some(QrModel::Model1),
+            0x32 => Some(QrModel::Model2),
+            _ => None,
+        }
+    }
+}
+
+impl QrErrorCorrection {
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0x30 => Some(QrErrorCorrection::L),
+            0x31 => Some(QrErrorCorrection::M),
+            0x32 => Some(QrErrorCorrection::Q),
+            0x33 => Some(QrErrorCorrection::H),
+            _ => None,
+        }
+    }
+}
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 pub const DEFAULT_WIDTH: usize = 384;
 pub const DEFAULT_CHAR_SPACING: usize = 2;
 pub const DEFAULT_FONTA_WIDTH: usize = 12;
@@ -5,3 +7,5 @@ pub const DEFAULT_FONTB_WIDTH: usize = 9;
 pub const DEFAULT_FONTC_WIDTH: usize = 9;
 pub const DEFAULT_FONTD_WIDTH: usize = 9;
 pub const DEFAULT_FONTE_WIDTH: usize = 9;
+pub const DEFAULT_DOUBLE_BYTE_WIDTH: usize = 24;
+pub const DEFAULT_STATUS_TIMEOUT: Duration = Duration::from_secs(1);
@@ -1,8 +1,10 @@
 pub mod default;
 pub mod tm_t20ii;
 
-use crate::command::Font;
+use crate::command::{CodeTable, Font};
+use crate::encoding::{DoubleByteEncoding, EncodingPolicy};
 use default::*;
+use std::time::Duration;
 
 #[derive(Clone, Debug)]
 pub struct FontWidths {
@@ -37,6 +39,16 @@ pub struct PrinterConfig {
     pub width: usize,
     pub char_spacing: usize,
     pub font_widths: FontWidths,
+    /// Time to wait for the printer to answer a status request
+    pub status_timeout: Duration,
+    /// How characters outside of the code table are printed
+    pub encoding_policy: EncodingPolicy,
+    /// Code tables the printer supports, earlier ones are preferred
+    pub code_tables: Vec<CodeTable>,
+    /// Encoding of the built-in double-byte font, `None` if there is none
+    pub double_byte: Option<DoubleByteEncoding>,
+    /// Width of double-byte characters in dots
+    pub double_byte_width: usize,
 }
 
 impl PrinterConfig {
@@ -52,6 +64,26 @@ impl PrinterConfig {
         self.char_spacing = char_spacing;
         self
     }
+    pub fn status_timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.status_timeout = timeout;
+        self
+    }
+    pub fn encoding_policy(&mut self, policy: EncodingPolicy) -> &mut Self {
+        self.encoding_policy = policy;
+        self
+    }
+    pub fn code_tables(&mut self, tables: Vec<CodeTable>) -> &mut Self {
+        self.code_tables = tables;
+        self
+    }
+    pub fn double_byte(&mut self, encoding: Option<DoubleByteEncoding>) -> &mut Self {
+        self.double_byte = encoding;
+        self
+    }
+    pub fn double_byte_width(&mut self, width: usize) -> &mut Self {
+        self.double_byte_width = width;
+        self
+    }
 }
 
 impl Default for PrinterConfig {
@@ -60,6 +92,11 @@ impl Default for PrinterConfig {
             width: DEFAULT_WIDTH,
             char_spacing: DEFAULT_CHAR_SPACING,
             font_widths: FontWidths::default(),
+            status_timeout: DEFAULT_STATUS_TIMEOUT,
+            encoding_policy: EncodingPolicy::default(),
+            code_tables: vec![CodeTable::default()],
+            double_byte: None,
+            double_byte_width: DEFAULT_DOUBLE_BYTE_WIDTH,
         }
     }
 }
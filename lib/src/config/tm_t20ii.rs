@@ -1,5 +1,5 @@
 use super::PrinterConfig;
-use crate::command::Font;
+use crate::command::{CodeTable, Font};
 
 pub const TM_T20II_80MM_WIDTH: usize = 576;
 pub const TM_T20II_58MM_WIDTH: usize = 420;
@@ -13,7 +13,8 @@ impl PrinterConfig {
         this.width(TM_T20II_80MM_WIDTH)
             .char_spacing(TM_T20II_CHAR_SPACING)
             .font_width(&Font::FontA, TM_T20II_FONTA_WIDTH)
-            .font_width(&Font::FontB, TM_T20II_FONTB_WIDTH);
+            .font_width(&Font::FontB, TM_T20II_FONTB_WIDTH)
+            .code_tables(CodeTable::ALL.to_vec());
         this
     }
 }
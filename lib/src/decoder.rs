@@ -0,0 +1,512 @@
+//! Decodes an ESC/POS byte stream back into commands, e.g. to inspect what a
+//! [`Printer`](crate::Printer) sent to its device.
+
+use crate::command::{
+    CharMagnification, Charset, CodeTable, Command, CutFunction, DrawerPin, Font, HriPosition,
+    Justification, QrErrorCorrection, QrModel, StatusBackMask, StatusRequest, UnderlineThickness,
+};
+use crate::instruction::RasterScale;
+
+const DLE: u8 = 0x10;
+const ESC: u8 = 0x1b;
+const FS: u8 = 0x1c;
+const GS: u8 = 0x1d;
+
+/// A bit image sent with ESC *
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bitmap {
+    /// Density mode `m`
+    pub mode: u8,
+    /// Number of dots horizontally
+    pub width: usize,
+    /// Column data, 1 byte per column for 8-dot modes or 3 for 24-dot modes
+    pub data: Vec<u8>,
+}
+
+/// A raster bit image sent with GS v 0
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RasterImage {
+    pub scale: RasterScale,
+    /// Number of bytes of each row
+    pub width_bytes: usize,
+    /// Number of rows
+    pub height: usize,
+    /// Rows of dots, most significant bit first
+    pub data: Vec<u8>,
+}
+
+/// A single item of a decoded byte stream
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Decoded {
+    Command(Command),
+    /// Printable bytes, including line feeds
+    Text(Vec<u8>),
+    Bitmap(Bitmap),
+    Raster(RasterImage),
+    /// A GS k barcode with the symbology byte `m` and its data
+    Barcode {
+        system: u8,
+        data: Vec<u8>,
+    },
+    /// Data stored in the QR code symbol storage area
+    QrData(Vec<u8>),
+    /// A sequence which is not understood
+    Unknown(Vec<u8>),
+}
+
+/// Iterator over the items of an ESC/POS byte stream
+#[derive(Debug, Clone)]
+pub struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+/// Decodes a whole ESC/POS byte stream
+pub fn decode(bytes: &[u8]) -> Vec<Decoded> {
+    Decoder::new(bytes).collect()
+}
+
+impl<'a> Decoder<'a> {
+    pub fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes, pos: 0 }
+    }
+
+    fn is_prefix(byte: u8) -> bool {
+        matches!(byte, DLE | ESC | FS | GS)
+    }
+
+    /// Returns the byte at `offset` from the current position
+    fn peek(&self, offset: usize) -> Option<u8> {
+        self.bytes.get(self.pos + offset).copied()
+    }
+
+    /// Consumes `len` bytes and returns them
+    fn take(&mut self, len: usize) -> &'a [u8] {
+        let end = (self.pos + len).min(self.bytes.len());
+        let taken = &self.bytes[self.pos..end];
+        self.pos = end;
+        taken
+    }
+
+    /// Consumes a command of `len` bytes and maps it to a command
+    fn command(&mut self, len: usize, cmd: Option<Command>) -> Decoded {
+        if self.pos + len > self.bytes.len() {
+            return Decoded::Unknown(self.take(len).to_vec());
+        }
+        let bytes = self.take(len);
+        match cmd {
+            Some(cmd) => Decoded::Command(cmd),
+            None => Decoded::Unknown(bytes.to_vec()),
+        }
+    }
+
+    fn decode_text(&mut self) -> Decoded {
+        let len = self.bytes[self.pos..]
+            .iter()
+            .position(|byte| Self::is_prefix(*byte))
+            .unwrap_or(self.bytes.len() - self.pos);
+        Decoded::Text(self.take(len).to_vec())
+    }
+
+    fn decode_esc(&mut self) -> Decoded {
+        let param = self.peek(2);
+        let cmd = match self.peek(1) {
+            Some(0x40) => return self.command(2, Some(Command::Init)),
+            Some(0x32) => return self.command(2, Some(Command::DefaultLineSpacing)),
+            Some(0x2a) => return self.decode_bitmap(),
+            Some(0x70) => {
+                let cmd = match (
+                    param.and_then(DrawerPin::from_byte),
+                    self.peek(3),
+                    self.peek(4),
+                ) {
+                    (Some(pin), Some(on), Some(off)) => Some(Command::DrawerKick(pin, on, off)),
+                    _ => None,
+                };
+                return self.command(5, cmd);
+            }
+            Some(0x42) => {
+                let cmd = param.zip(self.peek(3)).map(|(n, t)| Command::Beep(n, t));
+                return self.command(4, cmd);
+            }
+            Some(0x21) => param
+                .filter(|mode| *mode == 0)
+                .map(|_| Command::PrintModeDefault),
+            Some(0x52) => param.and_then(Charset::from_byte).map(Command::Charset),
+            Some(0x74) => param.and_then(CodeTable::from_byte).map(Command::CodeTable),
+            Some(0x4d) => param.and_then(Font::from_byte).map(Command::Font),
+            Some(0x2d) => param
+                .and_then(UnderlineThickness::from_byte)
+                .map(Command::Underline),
+            Some(0x45) => param.map(|n| Command::Bold(n & 1 == 1)),
+            Some(0x47) => param.map(|n| Command::DoubleStrike(n & 1 == 1)),
+            Some(0x4a) => param.map(Command::FeedPaper),
+            Some(0x64) => param.map(Command::FeedLines),
+            Some(0x33) => param.map(Command::LineSpacing),
+            Some(0x20) => param.map(Command::CharSpacing),
+            Some(0x61) => param
+                .and_then(Justification::from_byte)
+                .map(Command::Justification),
+            _ => return Decoded::Unknown(self.take(2).to_vec()),
+        };
+        self.command(3, cmd)
+    }
+
+    fn decode_gs(&mut self) -> Decoded {
+        let param = self.peek(2);
+        let cmd = match self.peek(1) {
+            Some(0x40) => return self.command(2, Some(Command::Init)),
+            Some(0x56) => {
+                // function A has no feed parameter
+                let len = if matches!(param, Some(0 | 1 | 48 | 49)) {
+                    3
+                } else {
+                    4
+                };
+                let cmd = param
+                    .and_then(|m| CutFunction::from_bytes(m, self.peek(3)))
+                    .map(|(cut_type, function)| Command::Cut(cut_type, function));
+                return self.command(len, cmd);
+            }
+            Some(0x4c) => {
+                let margin = self
+                    .peek(3)
+                    .zip(param)
+                    .map(|(high, low)| u16::from_le_bytes([low, high]));
+                return self.command(4, margin.map(Command::LeftMargin));
+            }
+            Some(0x6b) => return self.decode_barcode(),
+            Some(0x76) if param == Some(0x30) => return self.decode_raster(),
+            Some(0x28) if self.peek(2) == Some(0x6b) => return self.decode_qr(),
+            // other functions with a length, e.g. GS ( L graphics
+            Some(0x28) => {
+                let len = self
+                    .peek(3)
+                    .zip(self.peek(4))
+                    .map(|(low, high)| 5 + u16::from_le_bytes([low, high]) as usize);
+                return Decoded::Unknown(self.take(len.unwrap_or(self.bytes.len())).to_vec());
+            }
+            Some(0x38) if param == Some(0x4c) => {
+                let len = (3..7)
+                    .map(|offset| self.peek(offset))
+                    .collect::<Option<Vec<_>>>()
+                    .map(|len| 7 + u32::from_le_bytes([len[0], len[1], len[2], len[3]]) as usize);
+                return Decoded::Unknown(self.take(len.unwrap_or(self.bytes.len())).to_vec());
+            }
+            Some(0x42) => param.map(|n| Command::WhiteBlackReverse(n & 1 == 1)),
+            Some(0x61) => param
+                .map(StatusBackMask::from_byte)
+                .map(Command::AutoStatusBack),
+            Some(0x21) => param
+                .and_then(|n| CharMagnification::from_byte(n).ok())
+                .map(Command::CharSize),
+            Some(0x48) => param
+                .and_then(HriPosition::from_byte)
+                .map(Command::BarcodeHri),
+            Some(0x66) => param.and_then(Font::from_byte).map(Command::BarcodeHriFont),
+            Some(0x77) => param.map(Command::BarcodeWidth),
+            Some(0x68) => param.map(Command::BarcodeHeight),
+            _ => return Decoded::Unknown(self.take(2).to_vec()),
+        };
+        self.command(3, cmd)
+    }
+
+    fn decode_dle(&mut self) -> Decoded {
+        match self.peek(1) {
+            Some(0x04) => {
+                let cmd = self
+                    .peek(2)
+                    .and_then(StatusRequest::from_byte)
+                    .map(Command::TransmitStatus);
+                self.command(3, cmd)
+            }
+            Some(0x14) if self.peek(2) == Some(0x01) => {
+                let cmd = match (self.peek(3).and_then(DrawerPin::from_byte), self.peek(4)) {
+                    (Some(pin), Some(time)) => Some(Command::DrawerPulse(pin, time)),
+                    _ => None,
+                };
+                self.command(5, cmd)
+            }
+            _ => Decoded::Unknown(self.take(2).to_vec()),
+        }
+    }
+
+    fn decode_fs(&mut self) -> Decoded {
+        match self.peek(1) {
+            Some(0x26) => self.command(2, Some(Command::DoubleByteMode(true))),
+            Some(0x2e) => self.command(2, Some(Command::DoubleByteMode(false))),
+            Some(0x53) => {
+                let cmd = self
+                    .peek(2)
+                    .zip(self.peek(3))
+                    .map(|(left, right)| Command::DoubleByteSpacing(left, right));
+                self.command(4, cmd)
+            }
+            Some(0x57) => {
+                let cmd = self
+                    .peek(2)
+                    .map(|n| Command::DoubleByteQuadruple(n & 1 == 1));
+                self.command(3, cmd)
+            }
+            _ => Decoded::Unknown(self.take(2).to_vec()),
+        }
+    }
+
+    fn decode_bitmap(&mut self) -> Decoded {
+        let (mode, low, high) = match (self.peek(2), self.peek(3), self.peek(4)) {
+            (Some(mode), Some(low), Some(high)) => (mode, low, high),
+            _ => return Decoded::Unknown(self.take(self.bytes.len()).to_vec()),
+        };
+        let width = low as usize + high as usize * 256;
+        let data_len = match mode {
+            0 | 1 => width,
+            32 | 33 => width * 3,
+            _ => return Decoded::Unknown(self.take(3).to_vec()),
+        };
+        if self.pos + 5 + data_len > self.bytes.len() {
+            return Decoded::Unknown(self.take(self.bytes.len()).to_vec());
+        }
+        self.take(5);
+        Decoded::Bitmap(Bitmap {
+            mode,
+            width,
+            data: self.take(data_len).to_vec(),
+        })
+    }
+
+    fn decode_raster(&mut self) -> Decoded {
+        let header = (3..8)
+            .map(|offset| self.peek(offset))
+            .collect::<Option<Vec<_>>>();
+        let (scale, width_bytes, height) = match header.as_deref() {
+            Some([m, x_low, x_high, y_low, y_high]) => (
+                RasterScale::from_byte(*m),
+                u16::from_le_bytes([*x_low, *x_high]) as usize,
+                u16::from_le_bytes([*y_low, *y_high]) as usize,
+            ),
+            _ => return Decoded::Unknown(self.take(self.bytes.len()).to_vec()),
+        };
+        let scale = match scale {
+            Some(scale) => scale,
+            None => return Decoded::Unknown(self.take(4).to_vec()),
+        };
+        if self.pos + 8 + width_bytes * height > self.bytes.len() {
+            return Decoded::Unknown(self.take(self.bytes.len()).to_vec());
+        }
+        self.take(8);
+        Decoded::Raster(RasterImage {
+            scale,
+            width_bytes,
+            height,
+            data: self.take(width_bytes * height).to_vec(),
+        })
+    }
+
+    fn decode_barcode(&mut self) -> Decoded {
+        match (self.peek(2), self.peek(3)) {
+            // function B, the length is given by n
+            (Some(system @ 65..=79), Some(len))
+                if self.pos + 4 + len as usize <= self.bytes.len() =>
+            {
+                self.take(4);
+                Decoded::Barcode {
+                    system,
+                    data: self.take(len as usize).to_vec(),
+                }
+            }
+            _ => Decoded::Unknown(self.take(3).to_vec()),
+        }
+    }
+
+    fn decode_qr(&mut self) -> Decoded {
+        let (low, high, cn, func) = match (self.peek(3), self.peek(4), self.peek(5), self.peek(6)) {
+            (Some(low), Some(high), Some(cn), Some(func)) => (low, high, cn, func),
+            _ => return Decoded::Unknown(self.take(self.bytes.len()).to_vec()),
+        };
+        let len = 5 + low as usize + high as usize * 256;
+        if self.pos + len > self.bytes.len() {
+            return Decoded::Unknown(self.take(self.bytes.len()).to_vec());
+        }
+        let bytes = self.take(len);
+        let param = bytes.get(7).copied();
+        let cmd = match (cn, func) {
+            (0x31, 0x41) => param.and_then(QrModel::from_byte).map(Command::QrModel),
+            (0x31, 0x43) => param.map(Command::QrModuleSize),
+            (0x31, 0x45) => param
+                .and_then(QrErrorCorrection::from_byte)
+                .map(Command::QrErrorCorrection),
+            (0x31, 0x50) if param == Some(0x30) => {
+                return Decoded::QrData(bytes[8..].to_vec());
+            }
+            (0x31, 0x51) => Some(Command::QrPrint),
+            _ => None,
+        };
+        match cmd {
+            Some(cmd) => Decoded::Command(cmd),
+            None => Decoded::Unknown(bytes.to_vec()),
+        }
+    }
+}
+
+impl<'a> Iterator for Decoder<'a> {
+    type Item = Decoded;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let byte = self.peek(0)?;
+        Some(match byte {
+            ESC => self.decode_esc(),
+            GS => self.decode_gs(),
+            DLE => self.decode_dle(),
+            FS => self.decode_fs(),
+            _ => self.decode_text(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::CutType;
+    use crate::instruction::{EscposImage, ImageMode, ImageOptions, ImageWidth};
+
+    #[test]
+    fn round_trip_commands() {
+        let commands = vec![
+            Command::Cut(CutType::Full, CutFunction::FeedAndCut(0x96)),
+            Command::Cut(CutType::Partial, CutFunction::Cut),
+            Command::Cut(CutType::Partial, CutFunction::SetCutPosition(10)),
+            Command::Cut(CutType::Full, CutFunction::FeedToCutPosition(0)),
+            Command::Init,
+            Command::PrintModeDefault,
+            Command::Charset(Charset::Norway),
+            Command::CodeTable(CodeTable::Pc852),
+            Command::Font(Font::FontB),
+            Command::Underline(UnderlineThickness::TwoDot),
+            Command::Bold(true),
+            Command::DoubleStrike(false),
+            Command::WhiteBlackReverse(true),
+            Command::FeedPaper(12),
+            Command::FeedLines(3),
+            Command::LineSpacing(30),
+            Command::DefaultLineSpacing,
+            Command::CharSpacing(2),
+            Command::CharSize(CharMagnification::new(2, 3).unwrap()),
+            Command::LeftMargin(300),
+            Command::Justification(Justification::Right),
+            Command::BarcodeHri(HriPosition::Below),
+            Command::BarcodeHriFont(Font::FontA),
+            Command::BarcodeWidth(3),
+            Command::BarcodeHeight(80),
+            Command::QrModel(QrModel::Model2),
+            Command::QrModuleSize(6),
+            Command::QrErrorCorrection(QrErrorCorrection::Q),
+            Command::QrPrint,
+            Command::TransmitStatus(StatusRequest::Paper),
+            Command::AutoStatusBack(StatusBackMask::all()),
+            Command::DrawerKick(DrawerPin::Pin5, 25, 250),
+            Command::DrawerPulse(DrawerPin::Pin2, 2),
+            Command::Beep(3, 2),
+            Command::DoubleByteMode(true),
+            Command::DoubleByteSpacing(0, 2),
+            Command::DoubleByteQuadruple(true),
+            Command::DoubleByteMode(false),
+        ];
+        let bytes = commands
+            .iter()
+            .flat_map(|cmd| cmd.as_bytes())
+            .collect::<Vec<_>>();
+        let expected = commands
+            .into_iter()
+            .map(Decoded::Command)
+            .collect::<Vec<_>>();
+        assert_eq!(decode(&bytes), expected);
+    }
+
+    #[test]
+    fn text_and_unknown() {
+        let bytes = b"Hello\n\x1b\x99world\x1b";
+        assert_eq!(
+            decode(bytes),
+            vec![
+                Decoded::Text(b"Hello\n".to_vec()),
+                Decoded::Unknown(vec![0x1b, 0x99]),
+                Decoded::Text(b"world".to_vec()),
+                Decoded::Unknown(vec![0x1b]),
+            ]
+        );
+    }
+
+    #[test]
+    fn image() {
+        let img = image::DynamicImage::new_luma8(16, 16);
+        let bytes = EscposImage::new(&img, &ImageOptions::default()).as_bytes(16, None);
+        let decoded = decode(&bytes);
+        assert_eq!(
+            decoded.first(),
+            Some(&Decoded::Command(Command::LineSpacing(0)))
+        );
+        assert_eq!(
+            decoded.last(),
+            Some(&Decoded::Command(Command::DefaultLineSpacing))
+        );
+        let bitmaps = decoded
+            .iter()
+            .filter_map(|item| match item {
+                Decoded::Bitmap(bitmap) => Some(bitmap),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        assert!(!bitmaps.is_empty());
+        assert!(bitmaps
+            .iter()
+            .all(|bitmap| bitmap.mode == 1 && bitmap.width == 16 && bitmap.data.len() == 16));
+    }
+
+    #[test]
+    fn raster_image() -> crate::error::Result<()> {
+        let img = image::DynamicImage::new_luma8(16, 16);
+        let mut opts = ImageOptions::default();
+        opts.mode(ImageMode::Raster(RasterScale::DoubleWidth))
+            .band_height(10)?;
+        let bytes = EscposImage::new(&img, &opts).as_bytes(32, None);
+        let bands = decode(&bytes)
+            .into_iter()
+            .map(|item| match item {
+                Decoded::Raster(raster) => raster,
+                item => panic!("unexpected {:?}", item),
+            })
+            .collect::<Vec<_>>();
+        // 16 dots wide with double width dots, 32 rows split into bands
+        assert_eq!(bands.len(), 4);
+        assert!(bands
+            .iter()
+            .all(|band| band.scale == RasterScale::DoubleWidth
+                && band.width_bytes == 2
+                && band.data.iter().all(|byte| *byte == 0xff)));
+        assert_eq!(
+            bands.iter().map(|band| band.height).collect::<Vec<_>>(),
+            vec![10, 10, 10, 2]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn image_width() {
+        let img = image::DynamicImage::new_luma8(20, 10);
+        let raster = |width| {
+            let mut opts = ImageOptions::default();
+            opts.mode(ImageMode::Raster(RasterScale::DoubleHeight))
+                .width(width);
+            match decode(&EscposImage::new(&img, &opts).as_bytes(64, None)).as_slice() {
+                [Decoded::Raster(raster)] => (raster.width_bytes, raster.height),
+                decoded => panic!("unexpected {:?}", decoded),
+            }
+        };
+        // double height dots halve the rows
+        assert_eq!(raster(ImageWidth::Native), (3, 5));
+        assert_eq!(raster(ImageWidth::Max(16)), (2, 4));
+        assert_eq!(raster(ImageWidth::Dots(40)), (5, 10));
+        assert_eq!(raster(ImageWidth::Full), (8, 16));
+        assert_eq!(raster(ImageWidth::Dots(100)), (8, 16));
+    }
+}
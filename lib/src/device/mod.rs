@@ -0,0 +1,5 @@
+//! Devices to connect a [`Printer`](crate::Printer) to real printers.
+
+mod network;
+
+pub use network::{NetworkDevice, NetworkOptions, DEFAULT_PORT};
@@ -0,0 +1,277 @@
+use crate::error::Result;
+use crate::printer::BidirectionalDevice;
+use socket2::{SockRef, TcpKeepalive};
+use std::io::{self, Write};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::time::Duration;
+
+/// Port of the raw TCP printing service of network printers
+pub const DEFAULT_PORT: u16 = 9100;
+
+#[derive(Debug, Clone)]
+pub struct NetworkOptions {
+    connect_timeout: Option<Duration>,
+    write_timeout: Option<Duration>,
+    keepalive: Option<Duration>,
+    reconnect_attempts: usize,
+}
+
+impl NetworkOptions {
+    pub fn connect_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.connect_timeout = timeout;
+        self
+    }
+    pub fn write_timeout(&mut self, timeout: Option<Duration>) -> &mut Self {
+        self.write_timeout = timeout;
+        self
+    }
+    /// Enables TCP keepalive probes after the connection was idle for `idle`
+    pub fn keepalive(&mut self, idle: Option<Duration>) -> &mut Self {
+        self.keepalive = idle;
+        self
+    }
+    /// Number of times to retry connecting before a job is sent
+    pub fn reconnect_attempts(&mut self, attempts: usize) -> &mut Self {
+        self.reconnect_attempts = attempts;
+        self
+    }
+}
+
+impl Default for NetworkOptions {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Some(Duration::from_secs(5)),
+            write_timeout: Some(Duration::from_secs(10)),
+            keepalive: None,
+            reconnect_attempts: 1,
+        }
+    }
+}
+
+/// A printer reachable over raw TCP, usually on port [`DEFAULT_PORT`]
+///
+/// Written bytes are buffered and only sent as a single job on `flush`, so
+/// jobs of several clients cannot interleave. A pending job is not sent when
+/// the device is dropped, so call `flush` or [`finish`](Self::finish) first.
+/// If the printer closed the connection before the job is sent, the
+/// connection is reestablished. If sending fails part way, the error is
+/// returned and the job is kept, as resending it could print parts twice.
+#[derive(Debug)]
+pub struct NetworkDevice {
+    addrs: Vec<SocketAddr>,
+    opts: NetworkOptions,
+    stream: Option<TcpStream>,
+    buffer: Vec<u8>,
+}
+
+impl NetworkDevice {
+    /// Connects to the printer, trying each of the resolved addresses
+    pub fn connect(addr: impl ToSocketAddrs, opts: &NetworkOptions) -> Result<Self> {
+        let mut device = Self {
+            addrs: addr.to_socket_addrs()?.collect(),
+            opts: opts.clone(),
+            stream: None,
+            buffer: Vec::new(),
+        };
+        device.stream = Some(device.open()?);
+        Ok(device)
+    }
+
+    fn open(&self) -> io::Result<TcpStream> {
+        let mut last_err = None;
+        for addr in &self.addrs {
+            let stream = match self.opts.connect_timeout {
+                Some(timeout) => TcpStream::connect_timeout(addr, timeout),
+                None => TcpStream::connect(addr),
+            };
+            match stream {
+                Ok(stream) => {
+                    stream.set_write_timeout(self.opts.write_timeout)?;
+                    stream.set_nodelay(true)?;
+                    if let Some(idle) = self.opts.keepalive {
+                        SockRef::from(&stream)
+                            .set_tcp_keepalive(&TcpKeepalive::new().with_time(idle))?;
+                    }
+                    return Ok(stream);
+                }
+                Err(err) => last_err = Some(err),
+            }
+        }
+        Err(last_err.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "no address to connect to")
+        }))
+    }
+
+    /// Reconnects to the printer, dropping the current connection
+    pub fn reconnect(&mut self) -> Result<()> {
+        self.stream = None;
+        self.stream = Some(self.open()?);
+        Ok(())
+    }
+
+    /// Bytes of the job which have not been sent yet
+    pub fn pending(&self) -> &[u8] {
+        &self.buffer
+    }
+
+    /// Discards the pending job, e.g. after sending it failed part way
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+    }
+
+    /// Sends the pending job and closes the connection
+    pub fn finish(mut self) -> Result<()> {
+        self.flush()?;
+        Ok(())
+    }
+
+    pub fn peer_addr(&self) -> Option<SocketAddr> {
+        self.stream
+            .as_ref()
+            .and_then(|stream| stream.peer_addr().ok())
+    }
+
+    /// Whether the printer closed the connection, e.g. after it was idle
+    fn is_closed(stream: &TcpStream) -> bool {
+        let mut buf = [0];
+        let closed = stream.set_nonblocking(true).is_ok()
+            && match stream.peek(&mut buf) {
+                Ok(len) => len == 0,
+                Err(err) => err.kind() != io::ErrorKind::WouldBlock,
+            };
+        closed || stream.set_nonblocking(false).is_err()
+    }
+
+    /// The open connection, reconnecting if the printer closed it
+    fn connection(&mut self) -> io::Result<&mut TcpStream> {
+        if self.stream.as_ref().is_some_and(Self::is_closed) {
+            self.stream = None;
+        }
+        if self.stream.is_none() {
+            let mut res = self.open();
+            for _ in 0..self.opts.reconnect_attempts {
+                if res.is_ok() {
+                    break;
+                }
+                res = self.open();
+            }
+            self.stream = Some(res?);
+        }
+        self.stream
+            .as_mut()
+            .ok_or_else(|| io::ErrorKind::NotConnected.into())
+    }
+}
+
+impl io::Write for NetworkDevice {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buffer.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    /// Sends the buffered job to the printer
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let job = std::mem::take(&mut self.buffer);
+        let res = self
+            .connection()
+            .and_then(|stream| stream.write_all(&job).and_then(|_| stream.flush()));
+        if res.is_err() {
+            self.stream = None;
+            self.buffer = job;
+        }
+        res
+    }
+}
+
+impl BidirectionalDevice for NetworkDevice {
+    /// Sends the buffered job, so requests written before are received by
+    /// the printer, and reads its reply
+    fn read_timeout(&mut self, buf: &mut [u8], timeout: Duration) -> io::Result<usize> {
+        self.flush()?;
+        match &mut self.stream {
+            Some(stream) => stream.read_timeout(buf, timeout),
+            None => Err(io::ErrorKind::NotConnected.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PrinterConfig;
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    #[test]
+    fn send_job() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            stream.read_to_end(&mut received).unwrap();
+            received
+        });
+
+        let mut opts = NetworkOptions::default();
+        opts.keepalive(Some(Duration::from_secs(30)));
+        let device = NetworkDevice::connect(addr, &opts)?;
+        let mut printer = PrinterConfig::default().build(device)?;
+        printer.init()?.println("Hello")?;
+        assert_eq!(printer.device().pending(), b"\x1b@Hello\n");
+        printer.device_mut().flush()?;
+        assert!(printer.device().pending().is_empty());
+        drop(printer);
+
+        assert_eq!(server.join().unwrap(), b"\x1b@Hello\n");
+        Ok(())
+    }
+
+    #[test]
+    fn resend_after_disconnect() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let (closed_tx, closed_rx) = mpsc::channel();
+        let server = thread::spawn(move || {
+            // the first connection is closed right away
+            drop(listener.accept().unwrap());
+            closed_tx.send(()).unwrap();
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut received = Vec::new();
+            stream.read_to_end(&mut received).unwrap();
+            received
+        });
+
+        let device = NetworkDevice::connect(addr, &NetworkOptions::default())?;
+        let mut printer = PrinterConfig::default().build(device)?;
+        closed_rx.recv().unwrap();
+        printer.init()?.println("Hello")?.flush()?;
+        assert!(printer.device().pending().is_empty());
+        printer.println("again")?;
+        printer.into_device().finish()?;
+
+        assert_eq!(server.join().unwrap(), b"\x1b@Hello\nagain\n");
+        Ok(())
+    }
+
+    #[test]
+    fn keep_job_when_unreachable() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let server = thread::spawn(move || drop(listener.accept().unwrap()));
+
+        let mut device = NetworkDevice::connect(addr, &NetworkOptions::default())?;
+        server.join().unwrap();
+        device.write_all(b"Hello\n")?;
+        assert!(device.flush().is_err());
+        assert_eq!(device.pending(), b"Hello\n");
+        device.clear();
+        assert!(device.pending().is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,121 @@
+use crate::command::DrawerPin;
+use crate::error::{Error, Result};
+use crate::instruction::{Barcode, BarcodeSystem, Code128Set, QrCode, QrCodeOptions};
+use crate::printer::{Printer, PrinterDevice};
+use std::collections::HashMap;
+
+/// Pulse timings of the drawer directive in units of 2 ms
+const DRAWER_ON_TIME: u8 = 25;
+const DRAWER_OFF_TIME: u8 = 250;
+
+/// Action taken for a fenced code block whose info string starts with a
+/// registered directive name, e.g. ```` ```barcode ean13 ````
+#[derive(Debug, Clone)]
+pub enum CodeBlockDirective {
+    /// Prints the contents of the block as a QR code
+    QrCode(QrCodeOptions),
+    /// Prints the contents of the block as a barcode. The symbology is given
+    /// after the directive name and defaults to `code128`
+    Barcode,
+    /// Opens the cash drawer, the contents of the block are ignored. The pin
+    /// is given after the directive name as `2` or `5`, optionally preceded
+    /// by `pin`, and defaults to 2
+    Drawer,
+}
+
+pub(crate) fn default_directives() -> HashMap<String, CodeBlockDirective> {
+    let mut directives = HashMap::new();
+    directives.insert(
+        "qr".to_string(),
+        CodeBlockDirective::QrCode(QrCodeOptions::default()),
+    );
+    directives.insert("barcode".to_string(), CodeBlockDirective::Barcode);
+    directives.insert("drawer".to_string(), CodeBlockDirective::Drawer);
+    directives
+}
+
+/// Contents of a code block collected for a directive
+#[derive(Debug, Clone)]
+pub(crate) struct DirectiveBuffer {
+    directive: CodeBlockDirective,
+    args: String,
+    content: String,
+}
+
+impl DirectiveBuffer {
+    pub fn from_info(info: &str, directives: &HashMap<String, CodeBlockDirective>) -> Option<Self> {
+        let mut words = info.trim().splitn(2, char::is_whitespace);
+        let directive = directives.get(words.next()?)?;
+        Some(Self {
+            directive: directive.clone(),
+            args: words.next().unwrap_or("").trim().to_string(),
+            content: String::new(),
+        })
+    }
+
+    pub fn push_str(&mut self, text: &str) {
+        self.content.push_str(text);
+    }
+}
+
+impl<D> Printer<D>
+where
+    D: PrinterDevice,
+{
+    pub(crate) fn directive(&mut self, directive: &DirectiveBuffer) -> Result<&mut Self> {
+        let content = directive.content.trim_end_matches(['\r', '\n']);
+        match &directive.directive {
+            CodeBlockDirective::QrCode(opts) => self.qr_code(&QrCode::new(content, opts)?),
+            CodeBlockDirective::Barcode => {
+                let system = if directive.args.is_empty() {
+                    BarcodeSystem::Code128(Code128Set::B)
+                } else {
+                    directive.args.parse()?
+                };
+                self.barcode(&Barcode::new(system, content.trim())?)
+            }
+            CodeBlockDirective::Drawer => {
+                // `2`, `pin2` or `pin 2`
+                let args = &directive.args;
+                let pin = match args.strip_prefix("pin").unwrap_or(args).trim_start() {
+                    "" | "2" => DrawerPin::Pin2,
+                    "5" => DrawerPin::Pin5,
+                    _ => return Err(Error::InvalidDrawerPin(directive.args.clone())),
+                };
+                self.kick_drawer(pin, DRAWER_ON_TIME, DRAWER_OFF_TIME)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::Command;
+    use crate::PrinterConfig;
+
+    #[test]
+    fn drawer_pin() -> Result<()> {
+        let directives = default_directives();
+        for (info, pin) in [
+            ("drawer", DrawerPin::Pin2),
+            ("drawer 5", DrawerPin::Pin5),
+            ("drawer pin5", DrawerPin::Pin5),
+            ("drawer pin 2", DrawerPin::Pin2),
+        ] {
+            let mut printer = PrinterConfig::default().build(Vec::new())?;
+            printer.directive(&DirectiveBuffer::from_info(info, &directives).unwrap())?;
+            let kick = Command::DrawerKick(pin, DRAWER_ON_TIME, DRAWER_OFF_TIME);
+            assert_eq!(printer.device(), &kick.as_bytes());
+        }
+        for info in ["drawer pin 3", "drawer pinpin2"] {
+            let mut printer = PrinterConfig::default().build(Vec::new())?;
+            let directive = DirectiveBuffer::from_info(info, &directives).unwrap();
+            assert!(matches!(
+                printer.directive(&directive),
+                Err(Error::InvalidDrawerPin(pin)) if info.ends_with(&pin)
+            ));
+        }
+        Ok(())
+    }
+}
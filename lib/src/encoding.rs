@@ -0,0 +1,570 @@
+//! Encoding of text into the bytes of the printer's character code table
+
+#[cfg(feature = "bitmap-fallback")]
+use crate::bitmap_font::FONT_10X20_ISO_10646;
+use crate::command::CodeTable;
+use crate::config::PrinterConfig;
+use crate::error::{Error, Result};
+use crate::instruction::ImageMode;
+use oem_cp::code_table::DECODING_TABLE_CP_MAP;
+use oem_cp::code_table_type::TableType;
+use std::collections::HashMap;
+
+/// What happens to characters the code table cannot encode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EncodingPolicy {
+    /// Printing fails with [`Error::UnencodableChar`]
+    #[default]
+    Strict,
+    /// Characters are replaced by `?`
+    Replace,
+    /// Characters are replaced by their closest encodable form, e.g. `“` by
+    /// `"` and `ł` by `l`, and by `?` if there is none
+    Transliterate,
+    /// Characters are printed as an image of their glyph in the bundled
+    /// bitmap font, on a line of their own. The image mode selects between
+    /// ESC * and GS v 0. Characters without a glyph are replaced by `?`, as
+    /// are all characters without the `bitmap-fallback` feature.
+    Bitmap(ImageMode),
+}
+
+/// A character which was printed differently than it was written
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Substitution {
+    pub original: char,
+    pub replacement: String,
+}
+
+/// Encoding of the double-byte characters of printers with a built-in
+/// Kanji, Chinese or Korean font. The printer has to be set up for the same
+/// encoding, usually with its memory switches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DoubleByteEncoding {
+    /// Japanese
+    ShiftJis,
+    /// Simplified Chinese
+    Gb18030,
+    /// Traditional Chinese
+    Big5,
+    /// Korean
+    EucKr,
+}
+
+impl DoubleByteEncoding {
+    fn encoding(&self) -> &'static encoding_rs::Encoding {
+        match self {
+            Self::ShiftJis => encoding_rs::SHIFT_JIS,
+            Self::Gb18030 => encoding_rs::GB18030,
+            Self::Big5 => encoding_rs::BIG5,
+            Self::EucKr => encoding_rs::EUC_KR,
+        }
+    }
+
+    /// Returns the bytes which print the character in double-byte mode
+    pub fn encode(&self, c: char) -> Option<Vec<u8>> {
+        let mut buf = [0; 4];
+        let (bytes, _, unmappable) = self.encoding().encode(c.encode_utf8(&mut buf));
+        // single bytes, e.g. half-width katakana, are not double-byte
+        // characters and neither are the four byte sequences of GB18030
+        let is_double_byte = match (self, &*bytes) {
+            // KS X 1001, without the extensions of the Windows code page
+            (Self::EucKr, [first, second]) => {
+                (0xa1..=0xfe).contains(first) && (0xa1..=0xfe).contains(second)
+            }
+            (_, bytes) => bytes.len() == 2,
+        };
+        (!unmappable && is_double_byte).then(|| bytes.into_owned())
+    }
+
+    /// Decodes the character at the start of the bytes, returns it along with
+    /// the number of bytes it takes
+    pub fn decode(&self, bytes: &[u8]) -> Option<(char, usize)> {
+        let len = match (self, bytes.get(1)) {
+            (Self::Gb18030, Some(0x30..=0x39)) => 4,
+            _ => 2,
+        };
+        let decoded = self
+            .encoding()
+            .decode_without_bom_handling_and_without_replacement(bytes.get(..len)?)?;
+        let mut chars = decoded.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some((c, len)),
+            _ => None,
+        }
+    }
+}
+
+/// How a character is sent to the printer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CharMode {
+    /// ASCII, which is the same in every mode
+    Ascii,
+    SingleByte(CodeTable),
+    DoubleByte(DoubleByteEncoding),
+    /// Printed as an image, see [`EncodingPolicy::Bitmap`]
+    #[cfg(feature = "bitmap-fallback")]
+    Glyph(char),
+}
+
+impl CharMode {
+    fn encode(&self, c: char) -> Option<Vec<u8>> {
+        match self {
+            Self::Ascii => c.is_ascii().then(|| vec![c as u8]),
+            Self::SingleByte(table) => table.encode(c).map(|byte| vec![byte]),
+            Self::DoubleByte(encoding) => encoding.encode(c),
+            #[cfg(feature = "bitmap-fallback")]
+            Self::Glyph(_) => None,
+        }
+    }
+}
+
+/// The bytes of a single encoded character along with the mode they are
+/// printed in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct EncodedChar {
+    pub(crate) bytes: Vec<u8>,
+    pub(crate) mode: CharMode,
+}
+
+impl EncodedChar {
+    pub(crate) fn ascii(byte: u8) -> Self {
+        Self {
+            bytes: vec![byte],
+            mode: CharMode::Ascii,
+        }
+    }
+
+    /// Returns the byte of an ASCII character
+    pub(crate) fn as_ascii(&self) -> Option<u8> {
+        match self.mode {
+            CharMode::Ascii => self.bytes.first().copied(),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn is_full_width(&self) -> bool {
+        matches!(self.mode, CharMode::DoubleByte(_))
+    }
+}
+
+/// Encodes the text with the code tables and double-byte encoding of the
+/// config. Characters stay in the `current` mode where possible, otherwise
+/// the mode which encodes the longest run of the following text is picked,
+/// preferring code tables in the order they are listed. The double-byte
+/// encoding, which prints characters full-width, is only used for
+/// characters no code table can encode. Characters which cannot be encoded
+/// are handled according to the policy and appended to `substitutions`.
+pub(crate) fn encode(
+    text: &str,
+    config: &PrinterConfig,
+    mut current: CharMode,
+    substitutions: &mut Vec<Substitution>,
+) -> Result<Vec<EncodedChar>> {
+    let modes = config
+        .code_tables
+        .iter()
+        .copied()
+        .map(CharMode::SingleByte)
+        .chain(config.double_byte.map(CharMode::DoubleByte))
+        .collect::<Vec<_>>();
+    let mut encode_char = |c: char, rest: &str| -> Option<EncodedChar> {
+        if c.is_ascii() {
+            return Some(EncodedChar::ascii(c as u8));
+        }
+        let is_single_byte = |mode: &CharMode| matches!(mode, CharMode::SingleByte(_));
+        let single_byte = modes
+            .iter()
+            .any(|mode| is_single_byte(mode) && mode.encode(c).is_some());
+        let is_candidate =
+            |mode: &CharMode| mode.encode(c).is_some() && (!single_byte || is_single_byte(mode));
+        if !modes.contains(&current) || !is_candidate(&current) {
+            current = modes
+                .iter()
+                .rev()
+                .filter(|mode| is_candidate(mode))
+                .max_by_key(|mode| {
+                    rest.chars()
+                        .take_while(|c| c.is_ascii() || mode.encode(*c).is_some())
+                        .count()
+                })
+                .copied()?;
+        }
+        Some(EncodedChar {
+            bytes: current.encode(c)?,
+            mode: current,
+        })
+    };
+
+    let mut encoded = Vec::with_capacity(text.len());
+    for (idx, c) in text.char_indices() {
+        let rest = &text[idx + c.len_utf8()..];
+        if let Some(encoded_char) = encode_char(c, rest) {
+            encoded.push(encoded_char);
+            continue;
+        }
+        #[cfg(feature = "bitmap-fallback")]
+        if matches!(config.encoding_policy, EncodingPolicy::Bitmap(_))
+            && FONT_10X20_ISO_10646.has_glyph(c)
+        {
+            encoded.push(EncodedChar {
+                bytes: Vec::new(),
+                mode: CharMode::Glyph(c),
+            });
+            continue;
+        }
+        let substitution = substitute(c, config.encoding_policy, |c| {
+            c.is_ascii() || modes.iter().any(|mode| mode.encode(c).is_some())
+        })?;
+        for c in substitution.replacement.chars() {
+            encoded.push(encode_char(c, rest).unwrap_or_else(|| EncodedChar::ascii(b'?')));
+        }
+        substitutions.push(substitution);
+    }
+    Ok(encoded)
+}
+
+/// Finds the replacement of an unencodable character according to the policy
+fn substitute(
+    c: char,
+    policy: EncodingPolicy,
+    encodable: impl Fn(char) -> bool,
+) -> Result<Substitution> {
+    let replacement = match policy {
+        EncodingPolicy::Strict => return Err(Error::UnencodableChar(c)),
+        EncodingPolicy::Replace | EncodingPolicy::Bitmap(_) => None,
+        EncodingPolicy::Transliterate => {
+            transliterate(c).filter(|replacement| replacement.chars().all(&encodable))
+        }
+    };
+    Ok(Substitution {
+        original: c,
+        replacement: replacement.unwrap_or("?").to_string(),
+    })
+}
+
+impl CodeTable {
+    /// Returns the character printed for the byte
+    pub fn decode(&self, byte: u8) -> Option<char> {
+        if byte < 0x80 {
+            Some(byte as char)
+        } else {
+            CODE_TABLES[self].decoding[byte as usize - 0x80]
+        }
+    }
+
+    /// Returns the byte which prints the character
+    pub fn encode(&self, c: char) -> Option<u8> {
+        if c.is_ascii() {
+            Some(c as u8)
+        } else {
+            CODE_TABLES[self].encoding.get(&c).copied()
+        }
+    }
+
+    /// Upper half of the table, bytes `0x80..=0xff`
+    fn upper_half(&self) -> [Option<char>; 128] {
+        let mut chars = [None; 128];
+        let dos_page = match self {
+            Self::Pc437 => 437,
+            Self::Pc850 => 850,
+            Self::Pc860 => 860,
+            Self::Pc863 => 863,
+            Self::Pc865 => 865,
+            Self::Pc857 => 857,
+            Self::Pc737 => 737,
+            Self::Pc866 => 866,
+            Self::Pc852 => 852,
+            Self::Pc858 => 858,
+            Self::Pc720 => 720,
+            Self::Pc775 => 775,
+            Self::Pc855 => 855,
+            Self::Pc861 => 861,
+            Self::Pc862 => 862,
+            Self::Pc864 => 864,
+            Self::Pc869 => 869,
+            Self::Katakana => {
+                // half-width katakana at the same positions as in JIS X 0201
+                for byte in 0xa1..=0xdf {
+                    chars[byte - 0x80] = char::from_u32(0xff61 + (byte - 0xa1) as u32);
+                }
+                return chars;
+            }
+            _ => 0,
+        };
+        if let Some(table) = DECODING_TABLE_CP_MAP.get(&dos_page) {
+            for (idx, c) in chars.iter_mut().enumerate() {
+                *c = match table {
+                    TableType::Complete(table) => Some(table[idx]),
+                    TableType::Incomplete(table) => table[idx],
+                };
+            }
+            return chars;
+        }
+        let encoding = match self {
+            Self::Iso8859_2 => encoding_rs::ISO_8859_2,
+            Self::Iso8859_7 => encoding_rs::ISO_8859_7,
+            Self::Iso8859_15 => encoding_rs::ISO_8859_15,
+            Self::Wpc1250 => encoding_rs::WINDOWS_1250,
+            Self::Wpc1251 => encoding_rs::WINDOWS_1251,
+            Self::Wpc1252 => encoding_rs::WINDOWS_1252,
+            Self::Wpc1253 => encoding_rs::WINDOWS_1253,
+            Self::Wpc1254 => encoding_rs::WINDOWS_1254,
+            Self::Wpc1255 => encoding_rs::WINDOWS_1255,
+            Self::Wpc1256 => encoding_rs::WINDOWS_1256,
+            Self::Wpc1257 => encoding_rs::WINDOWS_1257,
+            Self::Wpc1258 => encoding_rs::WINDOWS_1258,
+            _ => unreachable!("{:?} is a DOS code page", self),
+        };
+        for (idx, c) in chars.iter_mut().enumerate() {
+            *c = encoding
+                .decode_without_bom_handling_and_without_replacement(&[0x80 + idx as u8])
+                .and_then(|decoded| decoded.chars().next())
+                // unassigned bytes decode to C1 control characters
+                .filter(|c| !c.is_control());
+        }
+        chars
+    }
+}
+
+/// Byte mapping of the upper half of a code table
+struct TableMapping {
+    decoding: [Option<char>; 128],
+    encoding: HashMap<char, u8>,
+}
+
+lazy_static! {
+    static ref CODE_TABLES: HashMap<CodeTable, TableMapping> = CodeTable::ALL
+        .iter()
+        .map(|table| {
+            let decoding = table.upper_half();
+            let encoding = decoding
+                .iter()
+                .enumerate()
+                .rev()
+                .filter_map(|(idx, c)| Some(((*c)?, 0x80 + idx as u8)))
+                .collect();
+            (*table, TableMapping { decoding, encoding })
+        })
+        .collect();
+}
+
+/// Closest ASCII form of a character
+pub fn transliterate(c: char) -> Option<&'static str> {
+    Some(match c {
+        // punctuation
+        '‘' | '’' | '‚' | '‛' | '′' | '‹' | '›' => "'",
+        '“' | '”' | '„' | '‟' | '″' => "\"",
+        '‐' | '‑' | '‒' | '–' | '−' => "-",
+        '—' | '―' => "--",
+        '…' => "...",
+        '•' | '·' | '∙' => "*",
+        '\u{a0}' | '\u{2002}'..='\u{200a}' | '\u{202f}' => " ",
+        '\u{200b}' | '\u{200c}' | '\u{200d}' | '\u{feff}' | '\u{ad}' => "",
+        // symbols
+        '€' => "EUR",
+        '£' => "GBP",
+        '¥' => "JPY",
+        '©' => "(c)",
+        '®' => "(R)",
+        '™' => "TM",
+        '×' => "x",
+        '÷' => "/",
+        '‰' => "%o",
+        '°' => "o",
+        '№' => "No",
+        '←' => "<-",
+        '→' => "->",
+        '↔' => "<->",
+        '≤' => "<=",
+        '≥' => ">=",
+        '≠' => "!=",
+        '±' => "+/-",
+        '½' => "1/2",
+        '¼' => "1/4",
+        '¾' => "3/4",
+        '✓' | '✔' => "v",
+        '✗' | '✘' => "x",
+        // latin letters
+        'À'..='Å' | 'Ā' | 'Ă' | 'Ą' => "A",
+        'à'..='å' | 'ā' | 'ă' | 'ą' => "a",
+        'Æ' => "AE",
+        'æ' => "ae",
+        'Ç' | 'Ć' | 'Ĉ' | 'Ċ' | 'Č' => "C",
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => "c",
+        'Ð' | 'Ď' | 'Đ' => "D",
+        'ð' | 'ď' | 'đ' => "d",
+        'È'..='Ë' | 'Ē' | 'Ĕ' | 'Ė' | 'Ę' | 'Ě' => "E",
+        'è'..='ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => "e",
+        'Ĝ' | 'Ğ' | 'Ġ' | 'Ģ' => "G",
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => "g",
+        'Ĥ' | 'Ħ' => "H",
+        'ĥ' | 'ħ' => "h",
+        'Ì'..='Ï' | 'Ĩ' | 'Ī' | 'Ĭ' | 'Į' | 'İ' => "I",
+        'ì'..='ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' | 'ı' => "i",
+        'Ĵ' => "J",
+        'ĵ' => "j",
+        'Ķ' => "K",
+        'ķ' => "k",
+        'Ĺ' | 'Ļ' | 'Ľ' | 'Ŀ' | 'Ł' => "L",
+        'ĺ' | 'ļ' | 'ľ' | 'ŀ' | 'ł' => "l",
+        'Ñ' | 'Ń' | 'Ņ' | 'Ň' => "N",
+        'ñ' | 'ń' | 'ņ' | 'ň' => "n",
+        'Ò'..='Ö' | 'Ø' | 'Ō' | 'Ŏ' | 'Ő' => "O",
+        'ò'..='ö' | 'ø' | 'ō' | 'ŏ' | 'ő' => "o",
+        'Œ' => "OE",
+        'œ' => "oe",
+        'Ŕ' | 'Ŗ' | 'Ř' => "R",
+        'ŕ' | 'ŗ' | 'ř' => "r",
+        'Ś' | 'Ŝ' | 'Ş' | 'Š' | 'Ș' => "S",
+        'ś' | 'ŝ' | 'ş' | 'š' | 'ș' => "s",
+        'ß' => "ss",
+        'Ţ' | 'Ť' | 'Ŧ' | 'Ț' => "T",
+        'ţ' | 'ť' | 'ŧ' | 'ț' => "t",
+        'Þ' => "Th",
+        'þ' => "th",
+        'Ù'..='Ü' | 'Ũ' | 'Ū' | 'Ŭ' | 'Ů' | 'Ű' | 'Ų' => "U",
+        'ù'..='ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => "u",
+        'Ŵ' => "W",
+        'ŵ' => "w",
+        'Ý' | 'Ŷ' | 'Ÿ' => "Y",
+        'ý' | 'ÿ' | 'ŷ' => "y",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bytes(encoded: &[EncodedChar]) -> Vec<u8> {
+        encoded.iter().flat_map(|c| c.bytes.clone()).collect()
+    }
+
+    fn modes(encoded: &[EncodedChar]) -> Vec<CharMode> {
+        encoded
+            .iter()
+            .map(|c| c.mode)
+            .filter(|mode| *mode != CharMode::Ascii)
+            .fold(Vec::new(), |mut modes, mode| {
+                if modes.last() != Some(&mode) {
+                    modes.push(mode);
+                }
+                modes
+            })
+    }
+
+    #[test]
+    fn policies() {
+        let text = "“Łódź” – 5€ 🍕";
+        let mut config = PrinterConfig::default();
+        let mut encode_as = |policy, substitutions: &mut Vec<Substitution>| {
+            config.encoding_policy(policy);
+            let current = CharMode::SingleByte(CodeTable::Pc437);
+            encode(text, &config, current, substitutions).map(|e| bytes(&e))
+        };
+        let mut substitutions = Vec::new();
+        assert!(matches!(
+            encode_as(EncodingPolicy::Strict, &mut substitutions),
+            Err(Error::UnencodableChar('“'))
+        ));
+        assert_eq!(
+            encode_as(EncodingPolicy::Replace, &mut substitutions).unwrap(),
+            b"??\xa2d?? ? 5? ?"
+        );
+        substitutions.clear();
+        assert_eq!(
+            encode_as(EncodingPolicy::Transliterate, &mut substitutions).unwrap(),
+            b"\"L\xa2dz\" - 5EUR ?"
+        );
+        assert_eq!(substitutions.len(), 7);
+        assert_eq!(
+            substitutions.last(),
+            Some(&Substitution {
+                original: '🍕',
+                replacement: "?".to_string()
+            })
+        );
+        // without the bundled font all characters are replaced
+        #[cfg(not(feature = "bitmap-fallback"))]
+        assert_eq!(
+            encode_as(
+                EncodingPolicy::Bitmap(ImageMode::Column24),
+                &mut substitutions
+            )
+            .unwrap(),
+            b"??\xa2d?? ? 5? ?"
+        );
+    }
+
+    #[test]
+    fn code_tables() {
+        assert_eq!(CodeTable::Pc852.encode('ł'), Some(0x88));
+        assert_eq!(CodeTable::Pc852.decode(0x88), Some('ł'));
+        assert_eq!(CodeTable::Wpc1252.encode('€'), Some(0x80));
+        assert_eq!(CodeTable::Wpc1252.decode(0x81), None);
+        assert_eq!(CodeTable::Katakana.decode(0xb1), Some('ｱ'));
+        for table in CodeTable::ALL {
+            for byte in 0..=0xff {
+                if let Some(c) = table.decode(byte) {
+                    assert_eq!(table.encode(c), Some(byte), "{:?} {:x}", table, byte);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn switches_modes() {
+        let mut config = PrinterConfig::default();
+        config
+            .code_tables(vec![CodeTable::Pc437, CodeTable::Pc852, CodeTable::Pc866])
+            .double_byte(Some(DoubleByteEncoding::ShiftJis));
+        let current = CharMode::SingleByte(CodeTable::Pc437);
+        let encoded = encode(
+            "Zażółć, привет ü 日本語 1°C",
+            &config,
+            current,
+            &mut Vec::new(),
+        )
+        .unwrap();
+        // ó is in PC437 too, but PC852 is kept for the rest of the word and °
+        // is printed with a code table though Shift JIS has it as well
+        assert_eq!(
+            modes(&encoded),
+            [
+                CharMode::SingleByte(CodeTable::Pc852),
+                CharMode::SingleByte(CodeTable::Pc866),
+                CharMode::SingleByte(CodeTable::Pc437),
+                CharMode::DoubleByte(DoubleByteEncoding::ShiftJis),
+                CharMode::SingleByte(CodeTable::Pc437),
+            ]
+        );
+        assert_eq!(encoded[2].bytes, [0xbe]);
+        assert_eq!(encoded[17].bytes, [0x93, 0xfa]);
+        assert_eq!(encoded[22].bytes, [0xf8]);
+    }
+
+    #[test]
+    fn double_byte() {
+        for (encoding, c) in [
+            (DoubleByteEncoding::ShiftJis, '漢'),
+            (DoubleByteEncoding::Gb18030, '汉'),
+            (DoubleByteEncoding::Big5, '漢'),
+            (DoubleByteEncoding::EucKr, '한'),
+        ] {
+            let bytes = encoding.encode(c).unwrap();
+            assert_eq!(encoding.decode(&bytes), Some((c, bytes.len())));
+        }
+        assert_eq!(DoubleByteEncoding::ShiftJis.encode('ｱ'), None);
+        // four bytes in GB18030
+        assert_eq!(DoubleByteEncoding::Gb18030.encode('ß'), None);
+        // only in the extension of the Windows code page
+        assert_eq!(DoubleByteEncoding::EucKr.encode('똠'), None);
+        assert_eq!(
+            DoubleByteEncoding::EucKr.encode('漢').map(|b| b.len()),
+            Some(2)
+        );
+        assert_eq!(DoubleByteEncoding::Big5.encode('🍕'), None);
+    }
+}
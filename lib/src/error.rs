@@ -1,15 +1,31 @@
+use crate::instruction::BarcodeSystem;
+
 pub type Result<T, E = Error> = std::result::Result<T, E>;
 
 #[derive(Error, Debug)]
 pub enum Error {
     #[error(transparent)]
     Io(#[from] std::io::Error),
-    #[error("Could not convert string to CP437")]
-    Cp437(codepage_437::Cp437Error),
+    #[error("Character '{}' cannot be encoded in the code table", _0)]
+    UnencodableChar(char),
     #[error(transparent)]
     Image(#[from] image::ImageError),
+    #[error("Image not found: {}", _0)]
+    ImageNotFound(String),
+    #[error("Could not load image {}: {}", _0, _1)]
+    ImageLoad(String, #[source] image::ImageError),
+    #[error("Invalid data URI, expected a base64 payload: {}", _0)]
+    InvalidDataUri(String),
     #[error("Image scale must be greater than 0 and less than or equal to 1")]
     InvalidImageScale,
+    #[error("Image brightness must be between -1 and 1 inclusive")]
+    InvalidImageBrightness,
+    #[error("Image contrast must be greater than or equal to 0")]
+    InvalidImageContrast,
+    #[error("Image gamma must be greater than 0")]
+    InvalidImageGamma,
+    #[error("Image band height must be greater than 0")]
+    InvalidBandHeight,
     #[error("Character magnification must greater than 0 and less than or equal to 8")]
     InvalidCharMagnification,
     #[error("Spacing must be between 0 and 255 inclusive")]
@@ -26,16 +42,38 @@ pub enum Error {
     EmptyRuleString,
     #[error("Markdown Event unimplemented: {:?}", _0)]
     MarkdownEventUnimplemented(pulldown_cmark::Event<'static>),
-}
-
-impl From<codepage_437::IntoCp437Error> for Error {
-    fn from(err: codepage_437::IntoCp437Error) -> Self {
-        err.cp437_error().into()
-    }
-}
-
-impl From<codepage_437::Cp437Error> for Error {
-    fn from(err: codepage_437::Cp437Error) -> Self {
-        Self::Cp437(err)
-    }
+    #[error("Table with {} columns does not fit the printable width", _0)]
+    TableTooWide(usize),
+    #[error("Invalid character {:?} for barcode {:?}", _1, _0)]
+    InvalidBarcodeChar(BarcodeSystem, char),
+    #[error("Invalid data length {} for barcode {:?}", _1, _0)]
+    InvalidBarcodeLength(BarcodeSystem, usize),
+    #[error("Invalid barcode system: {}", _0)]
+    InvalidBarcodeSystem(String),
+    #[error("Invalid check digit for barcode {:?}", _0)]
+    InvalidBarcodeCheckDigit(BarcodeSystem),
+    #[error("Barcode module width must be between 2 and 6 inclusive")]
+    InvalidBarcodeWidth,
+    #[error("Barcode height must be greater than 0")]
+    InvalidBarcodeHeight,
+    #[error("QR code module size must be between 1 and 16 inclusive")]
+    InvalidQrModuleSize,
+    #[error("QR code data length {} must be between 1 and {} inclusive", _0, _1)]
+    InvalidQrCodeLength(usize, usize),
+    #[error("Drawer pulse time must be between 1 and 8 inclusive")]
+    InvalidDrawerPulseTime,
+    #[error("Invalid drawer pin: {}", _0)]
+    InvalidDrawerPin(String),
+    #[error("Beep count and duration must be between 1 and 9 inclusive")]
+    InvalidBeepParam,
+    #[error("Printer did not answer the request in time")]
+    StatusTimeout,
+    #[error("Invalid status byte {:#04x}", _0)]
+    InvalidStatus(u8),
+    #[error("Graphics key must be 2 characters between 32 and 126: {:?}", _0)]
+    InvalidGraphicsKey(Vec<u8>),
+    #[error("Graphics size {}x{} exceeds 8192x2304 dots", _0, _1)]
+    InvalidGraphicsSize(u32, u32),
+    #[error("Invalid graphics reply: {:?}", _0)]
+    InvalidGraphicsReply(Vec<u8>),
 }
@@ -0,0 +1,131 @@
+//! Printing of characters no code table can encode as images of their glyphs
+
+use crate::bitmap_font::{BitmapFont, FONT_10X20_ISO_10646};
+use crate::encoding::{CharMode, EncodedChar, EncodingPolicy};
+use crate::error::Result;
+use crate::instruction::{BitMapAlgorithm, EscposImage, ImageOptions, ImageWidth};
+use crate::{Printer, PrinterDevice};
+use image::{DynamicImage, GrayImage, Luma};
+
+impl<D> Printer<D>
+where
+    D: PrinterDevice,
+{
+    /// Prints the text up to the last run of glyphs, each run as an image on
+    /// a line of its own, and returns the remaining text
+    pub(crate) fn print_glyph_runs(
+        &mut self,
+        mut content: Vec<EncodedChar>,
+    ) -> Result<Vec<EncodedChar>> {
+        let is_glyph = |ch: &EncodedChar| matches!(ch.mode, CharMode::Glyph(_));
+        while let Some(start) = content.iter().position(is_glyph) {
+            // spaces between glyphs are part of the run
+            let mut end = start + 1;
+            while let Some(next) = content[end..]
+                .iter()
+                .position(|ch| ch.as_ascii() != Some(b' '))
+                .map(|idx| idx + end)
+                .filter(|next| is_glyph(&content[*next]))
+            {
+                end = next + 1;
+            }
+            let rest = content.split_off(end);
+            let run = content.split_off(start);
+            self.print_encoded(content)?;
+            self.glyphs(&run)?;
+            content = rest;
+        }
+        Ok(content)
+    }
+
+    fn glyphs(&mut self, run: &[EncodedChar]) -> Result<&mut Self> {
+        let mode = match self.config.encoding_policy {
+            EncodingPolicy::Bitmap(mode) => mode,
+            _ => return Ok(self),
+        };
+        let chars = run
+            .iter()
+            .map(|ch| match ch.mode {
+                CharMode::Glyph(ch) => ch,
+                _ => ' ',
+            })
+            .collect::<Vec<_>>();
+        let strip = self.render_glyphs(&FONT_10X20_ISO_10646, &chars);
+        let mut opts = ImageOptions::default();
+        opts.width(ImageWidth::Native)
+            .bit_map_algorithm(BitMapAlgorithm::Threshold(128))
+            .filter_type(image::imageops::FilterType::Nearest)
+            .mode(mode);
+        if self.state.left_offset != 0 {
+            self.println("")?;
+        }
+        self.image(&EscposImage::new(&DynamicImage::ImageLuma8(strip), &opts))
+    }
+
+    /// Draws the characters in cells of the current font, scaled to its
+    /// height of about twice its width and magnified like printed text
+    fn render_glyphs(&self, font: &BitmapFont, chars: &[char]) -> GrayImage {
+        let font_width = self.config.font_widths.get(&self.state.font);
+        let font_height = font_width * 2;
+        let (mag_x, mag_y) = (
+            self.state.char_magnification.width() as usize,
+            self.state.char_magnification.height() as usize,
+        );
+        let cell_width = self.calc_char_size();
+        let mut strip = GrayImage::from_pixel(
+            (cell_width * chars.len()) as u32,
+            (font_height * mag_y) as u32,
+            Luma([255]),
+        );
+        for (idx, ch) in chars.iter().enumerate() {
+            for y in 0..font_height * mag_y {
+                for x in 0..font_width * mag_x {
+                    let gx = x / mag_x * font.height / font_height;
+                    let gy = y / mag_y * font.height / font_height;
+                    if font.pixel(*ch, gx, gy) {
+                        strip.put_pixel((idx * cell_width + x) as u32, y as u32, Luma([0]));
+                    }
+                }
+            }
+        }
+        strip
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::command::CharMagnification;
+    use crate::decoder::{decode, Decoded};
+    use crate::encoding::EncodingPolicy;
+    use crate::instruction::ImageMode;
+    use crate::{PrinterConfig, Result};
+
+    #[test]
+    fn glyph_runs() -> Result<()> {
+        let mut config = PrinterConfig::default();
+        config.encoding_policy(EncodingPolicy::Bitmap(ImageMode::Column24));
+        let mut printer = config.build(Vec::new())?;
+        printer
+            .char_size(CharMagnification::new(2, 1)?)?
+            .print("Snow ☃ ☃ and \u{10ffff}!")?;
+        assert_eq!(printer.substitutions().len(), 1);
+
+        let decoded = decode(printer.device());
+        let bitmap = decoded
+            .iter()
+            .find_map(|item| match item {
+                Decoded::Bitmap(bitmap) => Some(bitmap),
+                _ => None,
+            })
+            .unwrap();
+        // three cells of font A with spacing, twice as wide
+        assert_eq!(bitmap.width, 3 * (12 + 2) * 2);
+        assert_eq!(bitmap.mode, 33);
+        // 24 dots high, a single strip
+        assert_eq!(bitmap.data.len(), bitmap.width * 3);
+        assert!(bitmap.data.iter().any(|column| *column != 0));
+        assert!(decoded.contains(&Decoded::Text(b"Snow \n".to_vec())));
+        assert!(decoded.contains(&Decoded::Text(b" and ?!".to_vec())));
+        Ok(())
+    }
+}
@@ -0,0 +1,387 @@
+//! Graphics stored in the printer's NV or download memory with the
+//! GS ( L / GS 8 L functions, e.g. a logo printed on every receipt.
+
+use crate::error::{Error, Result};
+use crate::instruction::{EscposImage, RasterScale};
+use crate::printer::{BidirectionalDevice, Printer, PrinterDevice};
+use std::time::Instant;
+
+const MAX_WIDTH: u32 = 8192;
+const MAX_HEIGHT: u32 = 2304;
+
+/// Header of the blocks the printer replies with
+const REPLY_HEADER: u8 = 0x37;
+const ACK: u8 = 0x06;
+
+/// Memory graphics are stored in
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphicsMemory {
+    /// Non-volatile memory, graphics survive turning the printer off
+    Nv,
+    /// Download memory, graphics are lost when the printer is reset
+    Download,
+}
+
+impl GraphicsMemory {
+    fn key_list_fn(&self) -> u8 {
+        match self {
+            Self::Nv => 64,
+            Self::Download => 80,
+        }
+    }
+    fn delete_all_fn(&self) -> u8 {
+        match self {
+            Self::Nv => 65,
+            Self::Download => 81,
+        }
+    }
+    fn delete_fn(&self) -> u8 {
+        match self {
+            Self::Nv => 66,
+            Self::Download => 82,
+        }
+    }
+    fn define_fn(&self) -> u8 {
+        match self {
+            Self::Nv => 67,
+            Self::Download => 83,
+        }
+    }
+    fn print_fn(&self) -> u8 {
+        match self {
+            Self::Nv => 69,
+            Self::Download => 85,
+        }
+    }
+    fn remaining_fn(&self) -> u8 {
+        match self {
+            Self::Nv => 51,
+            Self::Download => 52,
+        }
+    }
+}
+
+/// Key code of stored graphics, two printable ASCII characters
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GraphicsKey(u8, u8);
+
+impl GraphicsKey {
+    pub fn new(kc1: u8, kc2: u8) -> Result<Self> {
+        if [kc1, kc2].iter().all(|kc| (32..=126).contains(kc)) {
+            Ok(Self(kc1, kc2))
+        } else {
+            Err(Error::InvalidGraphicsKey(vec![kc1, kc2]))
+        }
+    }
+
+    pub fn as_bytes(&self) -> [u8; 2] {
+        [self.0, self.1]
+    }
+}
+
+impl std::str::FromStr for GraphicsKey {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        match s.as_bytes() {
+            [kc1, kc2] => Self::new(*kc1, *kc2),
+            bytes => Err(Error::InvalidGraphicsKey(bytes.to_vec())),
+        }
+    }
+}
+
+/// Builds a graphics function, using GS 8 L if the parameters are too long
+/// for GS ( L
+fn graphics_fn(func: u8, params: &[u8]) -> Vec<u8> {
+    let len = params.len() + 2;
+    let mut res = if len <= u16::MAX as usize {
+        let mut res = vec![0x1d, 0x28, 0x4c];
+        res.extend_from_slice(&(len as u16).to_le_bytes());
+        res
+    } else {
+        let mut res = vec![0x1d, 0x38, 0x4c];
+        res.extend_from_slice(&(len as u32).to_le_bytes());
+        res
+    };
+    res.extend_from_slice(&[48, func]);
+    res.extend_from_slice(params);
+    res
+}
+
+impl<D> Printer<D>
+where
+    D: PrinterDevice,
+{
+    /// Stores the image under `key`, replacing graphics stored under it.
+    /// The image is converted as wide as it would be printed.
+    pub fn define_graphics(
+        &mut self,
+        memory: GraphicsMemory,
+        key: GraphicsKey,
+        image: &EscposImage,
+    ) -> Result<&mut Self> {
+        let (width, height, data) = image.raster(self.printable_width());
+        if width > MAX_WIDTH || height == 0 || height > MAX_HEIGHT {
+            return Err(Error::InvalidGraphicsSize(width, height));
+        }
+        // raster format with a single color
+        let mut params = vec![48];
+        params.extend_from_slice(&key.as_bytes());
+        params.push(1);
+        params.extend_from_slice(&(width as u16).to_le_bytes());
+        params.extend_from_slice(&(height as u16).to_le_bytes());
+        params.push(49);
+        params.extend_from_slice(&data);
+        unsafe { self.raw(graphics_fn(memory.define_fn(), &params)) }
+    }
+
+    /// Prints the graphics stored under `key`
+    pub fn print_graphics(
+        &mut self,
+        memory: GraphicsMemory,
+        key: GraphicsKey,
+        scale: RasterScale,
+    ) -> Result<&mut Self> {
+        // graphics are only printed at the beginning of a line
+        if self.state.left_offset != 0 {
+            self.println("")?;
+        }
+        let (dot_width, dot_height) = scale.dot_size();
+        let [kc1, kc2] = key.as_bytes();
+        let params = [kc1, kc2, dot_width as u8, dot_height as u8];
+        unsafe {
+            self.raw(graphics_fn(memory.print_fn(), &params))?;
+        }
+        self.state.left_offset = 0;
+        Ok(self)
+    }
+
+    pub fn delete_graphics(
+        &mut self,
+        memory: GraphicsMemory,
+        key: GraphicsKey,
+    ) -> Result<&mut Self> {
+        unsafe { self.raw(graphics_fn(memory.delete_fn(), &key.as_bytes())) }
+    }
+
+    pub fn delete_all_graphics(&mut self, memory: GraphicsMemory) -> Result<&mut Self> {
+        unsafe { self.raw(graphics_fn(memory.delete_all_fn(), b"CLR")) }
+    }
+}
+
+impl<D> Printer<D>
+where
+    D: BidirectionalDevice,
+{
+    /// Reads a reply block, from its header up to the terminating NUL
+    fn read_reply(&mut self) -> Result<Vec<u8>> {
+        let deadline = Instant::now() + self.config.status_timeout;
+        let mut reply = Vec::new();
+        let mut byte = [0];
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            if timeout.is_zero() || self.device.read_timeout(&mut byte, timeout)? == 0 {
+                return Err(Error::StatusTimeout);
+            }
+            match byte[0] {
+                // skip other data the printer sent, e.g. automatic status back
+                REPLY_HEADER if reply.is_empty() => reply.push(REPLY_HEADER),
+                _ if reply.is_empty() => {}
+                0x00 => return Ok(reply),
+                byte => reply.push(byte),
+            }
+        }
+    }
+
+    /// Sends a capacity request and parses the decimal number of the reply
+    fn graphics_capacity_reply(&mut self, func: u8) -> Result<usize> {
+        unsafe {
+            self.raw(graphics_fn(func, &[]))?;
+        }
+        let reply = self.read_reply()?;
+        // header and identifier are followed by ASCII digits
+        std::str::from_utf8(reply.get(2..).unwrap_or_default())
+            .ok()
+            .and_then(|digits| digits.parse().ok())
+            .ok_or(Error::InvalidGraphicsReply(reply))
+    }
+
+    /// Total capacity of the NV graphics memory in bytes
+    pub fn graphics_capacity(&mut self) -> Result<usize> {
+        self.graphics_capacity_reply(48)
+    }
+
+    /// Remaining capacity of the graphics memory in bytes
+    pub fn graphics_remaining(&mut self, memory: GraphicsMemory) -> Result<usize> {
+        self.graphics_capacity_reply(memory.remaining_fn())
+    }
+
+    /// Lists the keys of the stored graphics
+    pub fn graphics_keys(&mut self, memory: GraphicsMemory) -> Result<Vec<GraphicsKey>> {
+        unsafe {
+            self.raw(graphics_fn(memory.key_list_fn(), b"KC"))?;
+        }
+        let mut keys = Vec::new();
+        loop {
+            let reply = self.read_reply()?;
+            // header, identifier and whether more blocks follow
+            let (more, codes) = match reply.as_slice() {
+                [REPLY_HEADER, _, status @ (0x40 | 0x41), codes @ ..] if codes.len() % 2 == 0 => {
+                    (*status == 0x41, codes)
+                }
+                _ => return Err(Error::InvalidGraphicsReply(reply)),
+            };
+            for code in codes.chunks(2) {
+                keys.push(GraphicsKey::new(code[0], code[1])?);
+            }
+            if !more {
+                return Ok(keys);
+            }
+            unsafe {
+                self.raw([ACK])?;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::instruction::{ImageMode, ImageOptions};
+    use crate::PrinterConfig;
+    use std::collections::VecDeque;
+    use std::io;
+    use std::time::Duration;
+
+    struct MockDevice {
+        written: Vec<u8>,
+        replies: VecDeque<u8>,
+    }
+
+    impl io::Write for MockDevice {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl BidirectionalDevice for MockDevice {
+        fn read_timeout(&mut self, buf: &mut [u8], _: Duration) -> io::Result<usize> {
+            Ok(match self.replies.pop_front() {
+                Some(byte) => {
+                    buf[0] = byte;
+                    1
+                }
+                None => 0,
+            })
+        }
+    }
+
+    fn mock_printer(replies: &[u8]) -> Result<Printer<MockDevice>> {
+        let device = MockDevice {
+            written: Vec::new(),
+            replies: replies.to_vec().into(),
+        };
+        PrinterConfig::default().build(device)
+    }
+
+    #[test]
+    fn define_and_print() -> Result<()> {
+        let mut opts = ImageOptions::default();
+        opts.mode(ImageMode::Raster(RasterScale::Normal))
+            .scale(0.5)?;
+        let image = EscposImage::new(&image::DynamicImage::new_luma8(10, 5), &opts);
+        let key = "LG".parse()?;
+        let mut printer = PrinterConfig::default().width(20).build(Vec::new())?;
+        printer
+            .define_graphics(GraphicsMemory::Nv, key, &image)?
+            .print_graphics(GraphicsMemory::Nv, key, RasterScale::DoubleHeight)?;
+
+        // 10 dots wide and 5 high
+        let mut expected = vec![
+            0x1d, 0x28, 0x4c, 21, 0, 48, 67, 48, b'L', b'G', 1, 10, 0, 5, 0, 49,
+        ];
+        expected.extend([0xff, 0xc0].repeat(5));
+        expected.extend([0x1d, 0x28, 0x4c, 6, 0, 48, 69, b'L', b'G', 1, 2]);
+        assert_eq!(printer.device(), &expected);
+        assert!("L".parse::<GraphicsKey>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn capacity_replies() -> Result<()> {
+        // preceded by other data the printer sent
+        let mut printer = mock_printer(b"\x14\x37\x30262144\x00\x37\x3265536\x00")?;
+        assert_eq!(printer.graphics_capacity()?, 262144);
+        assert_eq!(printer.graphics_remaining(GraphicsMemory::Download)?, 65536);
+        assert_eq!(
+            printer.device().written,
+            b"\x1d\x28\x4c\x02\x00\x30\x30\x1d\x28\x4c\x02\x00\x30\x34"
+        );
+
+        let mut printer = mock_printer(b"\x37\x31many\x00")?;
+        assert!(matches!(
+            printer.graphics_remaining(GraphicsMemory::Nv),
+            Err(Error::InvalidGraphicsReply(reply)) if reply == b"\x37\x31many"
+        ));
+        // the reply is not terminated
+        let mut printer = mock_printer(b"\x37\x3012")?;
+        assert!(matches!(
+            printer.graphics_capacity(),
+            Err(Error::StatusTimeout)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn key_list_replies() -> Result<()> {
+        // two blocks, the first one followed by more
+        let mut printer = mock_printer(b"\x37\x72\x41LGAB\x00\x37\x72\x40xy\x00")?;
+        let keys = printer.graphics_keys(GraphicsMemory::Nv)?;
+        assert_eq!(keys, vec!["LG".parse()?, "AB".parse()?, "xy".parse()?]);
+        // the next block is requested with ACK
+        assert_eq!(
+            printer.device().written,
+            b"\x1d\x28\x4c\x04\x00\x30\x40KC\x06"
+        );
+
+        let mut printer = mock_printer(b"\x37\x72\x40\x00")?;
+        assert!(printer.graphics_keys(GraphicsMemory::Download)?.is_empty());
+        assert_eq!(printer.device().written, b"\x1d\x28\x4c\x04\x00\x30\x50KC");
+
+        for reply in [&b"\x37\x72\x40L\x00"[..], b"\x37\x72\x42LG\x00"] {
+            let mut printer = mock_printer(reply)?;
+            assert!(matches!(
+                printer.graphics_keys(GraphicsMemory::Nv),
+                Err(Error::InvalidGraphicsReply(_))
+            ));
+        }
+        let mut printer = mock_printer(b"\x37\x72\x40L\x1f\x00")?;
+        assert!(matches!(
+            printer.graphics_keys(GraphicsMemory::Nv),
+            Err(Error::InvalidGraphicsKey(_))
+        ));
+        // the second block never arrives
+        let mut printer = mock_printer(b"\x37\x72\x41LG\x00")?;
+        assert!(matches!(
+            printer.graphics_keys(GraphicsMemory::Nv),
+            Err(Error::StatusTimeout)
+        ));
+        Ok(())
+    }
+
+    #[test]
+    fn delete() -> Result<()> {
+        let mut printer = PrinterConfig::default().build(Vec::new())?;
+        printer
+            .delete_graphics(GraphicsMemory::Nv, "LG".parse()?)?
+            .delete_all_graphics(GraphicsMemory::Download)?;
+        assert_eq!(
+            printer.device(),
+            b"\x1d\x28\x4c\x04\x00\x30\x42LG\x1d\x28\x4c\x05\x00\x30\x51CLR"
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,284 @@
+use crate::error::{Error, Result};
+use std::str::FromStr;
+
+/// Barcode symbologies supported by GS k
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
+pub enum BarcodeSystem {
+    /// 11 digits, or 12 with the check digit
+    UpcA,
+    /// 6 digits, or 7, 8, 11, 12 digits starting with the number system 0
+    UpcE,
+    /// 12 digits, or 13 with the check digit
+    Ean13,
+    /// 7 digits, or 8 with the check digit
+    Ean8,
+    /// Digits, upper case letters, space and `$%*+-./`
+    Code39,
+    /// Interleaved 2 of 5, an even number of digits
+    Itf,
+    /// Digits and `$+-./:` between start and stop characters `A` to `D`
+    Codabar,
+    /// Any ASCII character
+    Code93,
+    /// Code 128 with the given starting code set
+    Code128(Code128Set),
+}
+
+/// Starting code set of a CODE128 barcode
+#[derive(Clone, Debug, Copy, PartialEq, Eq)]
+pub enum Code128Set {
+    /// ASCII control characters, digits and upper case letters
+    A,
+    /// Printable ASCII characters
+    B,
+    /// Pairs of digits
+    C,
+}
+
+impl BarcodeSystem {
+    /// Value of `m` for the GS k function B
+    pub fn to_byte(&self) -> u8 {
+        match self {
+            BarcodeSystem::UpcA => 65,
+            BarcodeSystem::UpcE => 66,
+            BarcodeSystem::Ean13 => 67,
+            BarcodeSystem::Ean8 => 68,
+            BarcodeSystem::Code39 => 69,
+            BarcodeSystem::Itf => 70,
+            BarcodeSystem::Codabar => 71,
+            BarcodeSystem::Code93 => 72,
+            BarcodeSystem::Code128(_) => 73,
+        }
+    }
+
+    fn check_len(&self, len: usize, valid: bool) -> Result<()> {
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::InvalidBarcodeLength(*self, len))
+        }
+    }
+
+    fn check_chars(&self, data: &str, valid: impl Fn(char) -> bool) -> Result<()> {
+        match data.chars().find(|ch| !valid(*ch)) {
+            Some(ch) => Err(Error::InvalidBarcodeChar(*self, ch)),
+            None => Ok(()),
+        }
+    }
+
+    /// Validates the data for the symbology and returns the bytes to send
+    fn encode(&self, data: &str) -> Result<Vec<u8>> {
+        let len = data.chars().count();
+        match self {
+            BarcodeSystem::UpcA => {
+                self.check_chars(data, |ch| ch.is_ascii_digit())?;
+                self.check_len(len, len == 11 || len == 12)?;
+                self.check_digit(data, 12)?;
+            }
+            BarcodeSystem::UpcE => {
+                self.check_chars(data, |ch| ch.is_ascii_digit())?;
+                self.check_len(len, matches!(len, 6 | 7 | 8 | 11 | 12))?;
+                if len != 6 && !data.starts_with('0') {
+                    return Err(Error::InvalidBarcodeChar(
+                        *self,
+                        data.chars().next().unwrap(),
+                    ));
+                }
+            }
+            BarcodeSystem::Ean13 => {
+                self.check_chars(data, |ch| ch.is_ascii_digit())?;
+                self.check_len(len, len == 12 || len == 13)?;
+                self.check_digit(data, 13)?;
+            }
+            BarcodeSystem::Ean8 => {
+                self.check_chars(data, |ch| ch.is_ascii_digit())?;
+                self.check_len(len, len == 7 || len == 8)?;
+                self.check_digit(data, 8)?;
+            }
+            BarcodeSystem::Code39 => {
+                self.check_chars(data, |ch| {
+                    ch.is_ascii_digit() || ch.is_ascii_uppercase() || " $%*+-./".contains(ch)
+                })?;
+                self.check_len(len, (1..=255).contains(&len))?;
+            }
+            BarcodeSystem::Itf => {
+                self.check_chars(data, |ch| ch.is_ascii_digit())?;
+                self.check_len(len, (2..=254).contains(&len) && len % 2 == 0)?;
+            }
+            BarcodeSystem::Codabar => {
+                self.check_chars(data, |ch| {
+                    ch.is_ascii_digit() || "ABCDabcd$+-./:".contains(ch)
+                })?;
+                self.check_len(len, (2..=255).contains(&len))?;
+                let is_start_stop = |ch: char| "ABCDabcd".contains(ch);
+                let mut inner = data.chars();
+                let (first, last) = (inner.next().unwrap(), inner.next_back().unwrap());
+                if !is_start_stop(first) {
+                    return Err(Error::InvalidBarcodeChar(*self, first));
+                }
+                if !is_start_stop(last) {
+                    return Err(Error::InvalidBarcodeChar(*self, last));
+                }
+                if let Some(ch) = inner.find(|ch| is_start_stop(*ch)) {
+                    return Err(Error::InvalidBarcodeChar(*self, ch));
+                }
+            }
+            BarcodeSystem::Code93 => {
+                self.check_chars(data, |ch| ch.is_ascii())?;
+                self.check_len(len, (1..=255).contains(&len))?;
+            }
+            BarcodeSystem::Code128(set) => return self.encode_code128(*set, data),
+        }
+        Ok(data.as_bytes().to_vec())
+    }
+
+    fn encode_code128(&self, set: Code128Set, data: &str) -> Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(data.len() + 2);
+        out.push(b'{');
+        match set {
+            Code128Set::A => {
+                self.check_chars(data, |ch| (ch as u32) < 96)?;
+                out.push(b'A');
+                out.extend_from_slice(data.as_bytes());
+            }
+            Code128Set::B => {
+                self.check_chars(data, |ch| (32..128).contains(&(ch as u32)))?;
+                out.push(b'B');
+                for byte in data.bytes() {
+                    // a literal `{` has to be escaped since it starts a function
+                    if byte == b'{' {
+                        out.push(b'{');
+                    }
+                    out.push(byte);
+                }
+            }
+            Code128Set::C => {
+                self.check_chars(data, |ch| ch.is_ascii_digit())?;
+                self.check_len(data.len(), data.len() % 2 == 0)?;
+                out.push(b'C');
+                for pair in data.as_bytes().chunks(2) {
+                    out.push((pair[0] - b'0') * 10 + (pair[1] - b'0'));
+                }
+            }
+        }
+        self.check_len(out.len(), out.len() <= 255)?;
+        Ok(out)
+    }
+
+    /// Verifies the check digit of UPC/EAN data if it is included
+    fn check_digit(&self, data: &str, full_len: usize) -> Result<()> {
+        if data.len() != full_len {
+            return Ok(());
+        }
+        let digits = data
+            .bytes()
+            .map(|byte| (byte - b'0') as u32)
+            .collect::<Vec<_>>();
+        let (check, payload) = digits.split_last().unwrap();
+        let sum: u32 = payload
+            .iter()
+            .rev()
+            .enumerate()
+            .map(|(idx, digit)| if idx % 2 == 0 { digit * 3 } else { *digit })
+            .sum();
+        if (10 - sum % 10) % 10 == *check {
+            Ok(())
+        } else {
+            Err(Error::InvalidBarcodeCheckDigit(*self))
+        }
+    }
+}
+
+impl FromStr for BarcodeSystem {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use BarcodeSystem::*;
+        Ok(match s.to_ascii_lowercase().replace('-', "").as_str() {
+            "upca" => UpcA,
+            "upce" => UpcE,
+            "ean13" => Ean13,
+            "ean8" => Ean8,
+            "code39" => Code39,
+            "itf" => Itf,
+            "codabar" => Codabar,
+            "code93" => Code93,
+            "code128" | "code128b" => Code128(Code128Set::B),
+            "code128a" => Code128(Code128Set::A),
+            "code128c" => Code128(Code128Set::C),
+            _ => return Err(Error::InvalidBarcodeSystem(s.to_string())),
+        })
+    }
+}
+
+/// A validated barcode ready to be printed with GS k
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Barcode {
+    system: BarcodeSystem,
+    data: Vec<u8>,
+}
+
+impl Barcode {
+    pub fn new(system: BarcodeSystem, data: impl AsRef<str>) -> Result<Self> {
+        Ok(Self {
+            system,
+            data: system.encode(data.as_ref())?,
+        })
+    }
+
+    pub fn system(&self) -> BarcodeSystem {
+        self.system
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut feed = vec![0x1d, 0x6b, self.system.to_byte(), self.data.len() as u8];
+        feed.extend_from_slice(&self.data);
+        feed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ean_check_digit() {
+        assert!(Barcode::new(BarcodeSystem::Ean13, "4006381333931").is_ok());
+        assert!(Barcode::new(BarcodeSystem::Ean13, "400638133393").is_ok());
+        assert!(matches!(
+            Barcode::new(BarcodeSystem::Ean13, "4006381333932"),
+            Err(Error::InvalidBarcodeCheckDigit(BarcodeSystem::Ean13))
+        ));
+        assert!(Barcode::new(BarcodeSystem::UpcA, "036000291452").is_ok());
+        assert!(Barcode::new(BarcodeSystem::Ean8, "96385074").is_ok());
+    }
+
+    #[test]
+    fn invalid_data() {
+        assert!(matches!(
+            Barcode::new(BarcodeSystem::Code39, "abc"),
+            Err(Error::InvalidBarcodeChar(BarcodeSystem::Code39, 'a'))
+        ));
+        assert!(matches!(
+            Barcode::new(BarcodeSystem::Itf, "123"),
+            Err(Error::InvalidBarcodeLength(BarcodeSystem::Itf, 3))
+        ));
+        assert!(Barcode::new(BarcodeSystem::Codabar, "A1234B").is_ok());
+        assert!(Barcode::new(BarcodeSystem::Codabar, "1234").is_err());
+    }
+
+    #[test]
+    fn code128() -> Result<()> {
+        let barcode = Barcode::new(BarcodeSystem::Code128(Code128Set::B), "a{1")?;
+        assert_eq!(
+            barcode.as_bytes(),
+            vec![0x1d, 0x6b, 73, 6, b'{', b'B', b'a', b'{', b'{', b'1']
+        );
+        let barcode = Barcode::new(BarcodeSystem::Code128(Code128Set::C), "1299")?;
+        assert_eq!(
+            barcode.as_bytes(),
+            vec![0x1d, 0x6b, 73, 4, b'{', b'C', 12, 99]
+        );
+        assert!(Barcode::new(BarcodeSystem::Code128(Code128Set::C), "123").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,328 @@
+//! Conversion of grayscale images to the black and white dots of the printer
+
+use image::GrayImage;
+
+/// How grayscale pixels are turned into black (0) and white (255) dots
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BitMapAlgorithm {
+    /// Pixels brighter than the threshold are white
+    Threshold(u8),
+    /// Threshold picked from the image's histogram with Otsu's method
+    Otsu,
+    /// Floyd–Steinberg error diffusion
+    Dithering,
+    Atkinson,
+    Stucki,
+    Burkes,
+    Sierra,
+    /// Ordered dithering with a 2x2 Bayer matrix
+    Bayer2,
+    /// Ordered dithering with a 4x4 Bayer matrix
+    Bayer4,
+    /// Ordered dithering with an 8x8 Bayer matrix
+    Bayer8,
+}
+
+/// Error diffusion kernels as (dx, dy, weight) and their divisor
+const FLOYD_STEINBERG: (&[(i32, u32, i32)], i32) =
+    (&[(1, 0, 7), (-1, 1, 3), (0, 1, 5), (1, 1, 1)], 16);
+// Atkinson only diffuses 6/8 of the error which keeps highlights clean
+const ATKINSON: (&[(i32, u32, i32)], i32) = (
+    &[
+        (1, 0, 1),
+        (2, 0, 1),
+        (-1, 1, 1),
+        (0, 1, 1),
+        (1, 1, 1),
+        (0, 2, 1),
+    ],
+    8,
+);
+const STUCKI: (&[(i32, u32, i32)], i32) = (
+    &[
+        (1, 0, 8),
+        (2, 0, 4),
+        (-2, 1, 2),
+        (-1, 1, 4),
+        (0, 1, 8),
+        (1, 1, 4),
+        (2, 1, 2),
+        (-2, 2, 1),
+        (-1, 2, 2),
+        (0, 2, 4),
+        (1, 2, 2),
+        (2, 2, 1),
+    ],
+    42,
+);
+const BURKES: (&[(i32, u32, i32)], i32) = (
+    &[
+        (1, 0, 8),
+        (2, 0, 4),
+        (-2, 1, 2),
+        (-1, 1, 4),
+        (0, 1, 8),
+        (1, 1, 4),
+        (2, 1, 2),
+    ],
+    32,
+);
+const SIERRA: (&[(i32, u32, i32)], i32) = (
+    &[
+        (1, 0, 5),
+        (2, 0, 3),
+        (-2, 1, 2),
+        (-1, 1, 4),
+        (0, 1, 5),
+        (1, 1, 4),
+        (2, 1, 2),
+        (-1, 2, 2),
+        (0, 2, 3),
+        (1, 2, 2),
+    ],
+    32,
+);
+
+impl BitMapAlgorithm {
+    /// Converts every pixel of the image to black or white
+    pub(crate) fn apply(&self, img: &mut GrayImage) {
+        match *self {
+            Self::Threshold(threshold) => threshold_image(img, threshold),
+            Self::Otsu => threshold_image(img, otsu_threshold(img)),
+            Self::Dithering => diffuse(img, FLOYD_STEINBERG),
+            Self::Atkinson => diffuse(img, ATKINSON),
+            Self::Stucki => diffuse(img, STUCKI),
+            Self::Burkes => diffuse(img, BURKES),
+            Self::Sierra => diffuse(img, SIERRA),
+            Self::Bayer2 => ordered(img, 2),
+            Self::Bayer4 => ordered(img, 4),
+            Self::Bayer8 => ordered(img, 8),
+        }
+    }
+}
+
+/// Brightness, contrast, gamma and inversion applied before the conversion
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct Tone {
+    pub(crate) brightness: f64,
+    pub(crate) contrast: f64,
+    pub(crate) gamma: f64,
+    pub(crate) invert: bool,
+}
+
+impl Default for Tone {
+    fn default() -> Self {
+        Self {
+            brightness: 0.,
+            contrast: 1.,
+            gamma: 1.,
+            invert: false,
+        }
+    }
+}
+
+impl Tone {
+    pub(crate) fn apply(&self, img: &mut GrayImage) {
+        if *self == Self::default() {
+            return;
+        }
+        let mut lut = [0; 256];
+        for (intensity, out) in lut.iter_mut().enumerate() {
+            let value = intensity as f64 / 255. + self.brightness;
+            let value = ((value - 0.5) * self.contrast + 0.5).clamp(0., 1.);
+            let value = value.powf(1. / self.gamma);
+            let value = if self.invert { 1. - value } else { value };
+            *out = (value * 255.).round() as u8;
+        }
+        for pixel in img.pixels_mut() {
+            pixel[0] = lut[pixel[0] as usize];
+        }
+    }
+}
+
+fn threshold_image(img: &mut GrayImage, threshold: u8) {
+    for pixel in img.pixels_mut() {
+        pixel[0] = if pixel[0] > threshold { 255 } else { 0 };
+    }
+}
+
+/// Threshold maximizing the variance between the dark and the light pixels
+fn otsu_threshold(img: &GrayImage) -> u8 {
+    let mut histogram = [0u64; 256];
+    for pixel in img.pixels() {
+        histogram[pixel[0] as usize] += 1;
+    }
+    let total = img.pixels().len() as f64;
+    let sum = histogram
+        .iter()
+        .enumerate()
+        .map(|(intensity, count)| intensity as f64 * *count as f64)
+        .sum::<f64>();
+
+    let (mut best, mut best_variance) = (0, 0.);
+    let (mut dark_count, mut dark_sum) = (0., 0.);
+    for (threshold, count) in histogram.iter().enumerate() {
+        dark_count += *count as f64;
+        dark_sum += threshold as f64 * *count as f64;
+        let light_count = total - dark_count;
+        if dark_count == 0. || light_count == 0. {
+            continue;
+        }
+        let dark_mean = dark_sum / dark_count;
+        let light_mean = (sum - dark_sum) / light_count;
+        let variance = dark_count * light_count * (dark_mean - light_mean).powi(2);
+        if variance > best_variance {
+            best = threshold;
+            best_variance = variance;
+        }
+    }
+    best as u8
+}
+
+/// Error diffusion, the quantization error of each pixel is spread over
+/// its unprocessed neighbours
+fn diffuse(img: &mut GrayImage, (kernel, divisor): (&[(i32, u32, i32)], i32)) {
+    let (width, height) = img.dimensions();
+    let mut values = img
+        .pixels()
+        .map(|pixel| pixel[0] as i32)
+        .collect::<Vec<_>>();
+    for y in 0..height {
+        for x in 0..width {
+            let index = (y * width + x) as usize;
+            let value = values[index];
+            let dot = if value > 127 { 255 } else { 0 };
+            let error = value - dot;
+            values[index] = dot;
+            for (dx, dy, weight) in kernel {
+                let (nx, ny) = (x as i32 + dx, y + dy);
+                if nx >= 0 && (nx as u32) < width && ny < height {
+                    values[(ny * width + nx as u32) as usize] += error * weight / divisor;
+                }
+            }
+        }
+    }
+    for (pixel, value) in img.pixels_mut().zip(values) {
+        pixel[0] = value as u8;
+    }
+}
+
+/// Bayer matrix of size `n`, a power of two, with the values 0..n²
+fn bayer_matrix(n: u32) -> Vec<u32> {
+    if n == 1 {
+        return vec![0];
+    }
+    let half = n / 2;
+    let smaller = bayer_matrix(half);
+    let mut matrix = vec![0; (n * n) as usize];
+    for y in 0..n {
+        for x in 0..n {
+            let base = 4 * smaller[((y % half) * half + x % half) as usize];
+            let offset = match (x < half, y < half) {
+                (true, true) => 0,
+                (false, true) => 2,
+                (true, false) => 3,
+                (false, false) => 1,
+            };
+            matrix[(y * n + x) as usize] = base + offset;
+        }
+    }
+    matrix
+}
+
+fn ordered(img: &mut GrayImage, n: u32) {
+    let matrix = bayer_matrix(n);
+    let levels = (n * n) as f64;
+    for (x, y, pixel) in img.enumerate_pixels_mut() {
+        let threshold = (matrix[((y % n) * n + x % n) as usize] as f64 + 0.5) * 256. / levels;
+        pixel[0] = if pixel[0] as f64 >= threshold { 255 } else { 0 };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Horizontal gradient from black to white
+    fn gradient(width: u32, height: u32) -> GrayImage {
+        GrayImage::from_fn(width, height, |x, _| [(x * 255 / (width - 1)) as u8].into())
+    }
+
+    fn black_ratio(img: &GrayImage) -> f64 {
+        img.pixels().filter(|pixel| pixel[0] == 0).count() as f64 / img.pixels().len() as f64
+    }
+
+    #[test]
+    fn bayer() {
+        assert_eq!(bayer_matrix(2), vec![0, 2, 3, 1]);
+        let matrix = bayer_matrix(8);
+        let mut sorted = matrix.clone();
+        sorted.sort_unstable();
+        assert_eq!(sorted, (0..64).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn algorithms_preserve_tone() {
+        use BitMapAlgorithm::*;
+        for algorithm in [
+            Otsu, Dithering, Atkinson, Stucki, Burkes, Sierra, Bayer2, Bayer4, Bayer8,
+        ] {
+            let mut img = gradient(64, 16);
+            algorithm.apply(&mut img);
+            assert!(img.pixels().all(|pixel| pixel[0] == 0 || pixel[0] == 255));
+            let ratio = black_ratio(&img);
+            assert!((0.4..0.6).contains(&ratio), "{:?}: {}", algorithm, ratio);
+
+            // flat gray is dithered, not thresholded
+            let mut gray = GrayImage::from_pixel(16, 16, [128].into());
+            algorithm.apply(&mut gray);
+            if algorithm != Otsu {
+                assert!((0.3..0.7).contains(&black_ratio(&gray)), "{:?}", algorithm);
+            }
+        }
+    }
+
+    #[test]
+    fn otsu() {
+        let img = GrayImage::from_fn(8, 1, |x, _| [if x < 4 { 40 } else { 200 }].into());
+        let threshold = otsu_threshold(&img);
+        assert!((40..200).contains(&threshold));
+    }
+
+    #[test]
+    fn tone() {
+        let mut img = GrayImage::from_raw(3, 1, vec![0, 128, 255]).unwrap();
+        Tone {
+            invert: true,
+            ..Default::default()
+        }
+        .apply(&mut img);
+        assert_eq!(img.as_raw(), &vec![255, 127, 0]);
+
+        let mut img = GrayImage::from_raw(3, 1, vec![0, 128, 255]).unwrap();
+        Tone {
+            brightness: 0.5,
+            ..Default::default()
+        }
+        .apply(&mut img);
+        assert_eq!(img.as_raw(), &vec![128, 255, 255]);
+
+        let mut img = GrayImage::from_raw(3, 1, vec![64, 128, 192]).unwrap();
+        Tone {
+            contrast: 0.,
+            ..Default::default()
+        }
+        .apply(&mut img);
+        assert_eq!(img.as_raw(), &vec![128, 128, 128]);
+
+        let mut img = GrayImage::from_raw(3, 1, vec![0, 64, 255]).unwrap();
+        Tone {
+            gamma: 2.,
+            ..Default::default()
+        }
+        .apply(&mut img);
+        assert_eq!(img.as_raw()[0], 0);
+        assert!(img.as_raw()[1] > 64);
+        assert_eq!(img.as_raw()[2], 255);
+    }
+}
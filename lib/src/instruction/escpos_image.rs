@@ -1,18 +1,103 @@
-use crate::command::{Command, Justification};
+use super::dither::{BitMapAlgorithm, Tone};
+use crate::command::Command;
 use crate::error::{Error, Result};
-use image::Pixel;
 
-#[derive(Debug, Clone, Copy)]
-pub enum BitMapAlgorithm {
-    Threshold(u8),
-    Dithering,
+/// Rows of a raster band, small enough for the receive buffer of most printers
+const DEFAULT_BAND_HEIGHT: u32 = 256;
+
+/// Scaling of raster bit images, the `m` parameter of GS v 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[repr(u8)]
+pub enum RasterScale {
+    #[default]
+    Normal = 0,
+    DoubleWidth = 1,
+    DoubleHeight = 2,
+    Quadruple = 3,
+}
+
+impl RasterScale {
+    pub fn from_byte(byte: u8) -> Option<Self> {
+        match byte {
+            0 | 48 => Some(Self::Normal),
+            1 | 49 => Some(Self::DoubleWidth),
+            2 | 50 => Some(Self::DoubleHeight),
+            3 | 51 => Some(Self::Quadruple),
+            _ => None,
+        }
+    }
+
+    /// Width and height of a printed dot
+    pub fn dot_size(&self) -> (u32, u32) {
+        match self {
+            Self::Normal => (1, 1),
+            Self::DoubleWidth => (2, 1),
+            Self::DoubleHeight => (1, 2),
+            Self::Quadruple => (2, 2),
+        }
+    }
+}
+
+/// How images are sent to the printer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageMode {
+    /// ESC * m = 1, strips of 8 dot columns printed at a third of the
+    /// vertical density
+    #[default]
+    Column8,
+    /// ESC * m = 33, strips of 24 dot columns at full density
+    Column24,
+    /// GS v 0, bands of raster rows
+    Raster(RasterScale),
+}
+
+impl ImageMode {
+    /// Width and height of a printed dot
+    pub fn dot_size(&self) -> (u32, u32) {
+        match self {
+            Self::Column8 => (1, 3),
+            Self::Column24 => (1, 1),
+            Self::Raster(scale) => scale.dot_size(),
+        }
+    }
+}
+
+/// Width an image is printed at, always limited to the printable width
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ImageWidth {
+    /// The printable width
+    #[default]
+    Full,
+    /// A width in dots
+    Dots(u32),
+    /// The width of the image, one pixel per dot, but at most this many dots
+    Max(u32),
+    /// The width of the image, one pixel per dot
+    Native,
+}
+
+impl ImageWidth {
+    /// Width in dots of an image `im_width` pixels wide
+    fn dots(&self, im_width: u32, printer_width: u32) -> u32 {
+        let width = match *self {
+            Self::Full => printer_width,
+            Self::Dots(dots) => dots,
+            Self::Max(dots) => im_width.min(dots),
+            Self::Native => im_width,
+        };
+        width.min(printer_width)
+    }
 }
 
 #[derive(Debug, Clone)]
 pub struct ImageOptions {
     bit_map_algorithm: BitMapAlgorithm,
+    width: ImageWidth,
     scale: f64,
+    tone: Tone,
     filter_type: image::imageops::FilterType,
+    mode: ImageMode,
+    band_height: u32,
 }
 
 impl ImageOptions {
@@ -20,6 +105,12 @@ impl ImageOptions {
         self.bit_map_algorithm = bit_map_algorithm;
         self
     }
+    pub fn width(&mut self, width: ImageWidth) -> &mut Self {
+        self.width = width;
+        self
+    }
+    /// Factor applied to the width, e.g. `0.5` prints a full width image
+    /// across half of the paper
     pub fn scale(&mut self, scale: f64) -> Result<&mut Self> {
         if scale <= 0. || scale > 1. {
             Err(Error::InvalidImageScale)
@@ -28,18 +119,69 @@ impl ImageOptions {
             Ok(self)
         }
     }
+    /// Added to the intensity of every pixel, from -1 (black) to 1 (white)
+    pub fn brightness(&mut self, brightness: f64) -> Result<&mut Self> {
+        if !(-1. ..=1.).contains(&brightness) {
+            Err(Error::InvalidImageBrightness)
+        } else {
+            self.tone.brightness = brightness;
+            Ok(self)
+        }
+    }
+    /// Factor the intensities are stretched by around mid gray, `1` leaves
+    /// the image unchanged
+    pub fn contrast(&mut self, contrast: f64) -> Result<&mut Self> {
+        if contrast < 0. || !contrast.is_finite() {
+            Err(Error::InvalidImageContrast)
+        } else {
+            self.tone.contrast = contrast;
+            Ok(self)
+        }
+    }
+    /// Gamma correction, values above 1 lighten the mid tones
+    pub fn gamma(&mut self, gamma: f64) -> Result<&mut Self> {
+        if gamma <= 0. || !gamma.is_finite() {
+            Err(Error::InvalidImageGamma)
+        } else {
+            self.tone.gamma = gamma;
+            Ok(self)
+        }
+    }
+    /// Prints light pixels black and dark pixels white
+    pub fn invert(&mut self, invert: bool) -> &mut Self {
+        self.tone.invert = invert;
+        self
+    }
     pub fn filter_type(&mut self, filter_type: image::imageops::FilterType) -> &mut Self {
         self.filter_type = filter_type;
         self
     }
+    pub fn mode(&mut self, mode: ImageMode) -> &mut Self {
+        self.mode = mode;
+        self
+    }
+    /// Maximum number of rows sent in a single raster command, images which
+    /// are taller are split into several bands to fit the printer's buffer
+    pub fn band_height(&mut self, band_height: u32) -> Result<&mut Self> {
+        if band_height == 0 {
+            Err(Error::InvalidBandHeight)
+        } else {
+            self.band_height = band_height;
+            Ok(self)
+        }
+    }
 }
 
 impl Default for ImageOptions {
     fn default() -> Self {
         Self {
             bit_map_algorithm: BitMapAlgorithm::Dithering,
+            width: ImageWidth::default(),
             scale: 1.,
+            tone: Tone::default(),
             filter_type: image::imageops::FilterType::Gaussian,
+            mode: ImageMode::default(),
+            band_height: DEFAULT_BAND_HEIGHT,
         }
     }
 }
@@ -57,96 +199,62 @@ impl EscposImage {
         }
     }
 
-    pub fn as_bytes(
-        &self,
-        printer_width: usize,
-        justification: Justification,
-        line_spacing: Option<u8>,
-    ) -> Vec<u8> {
-        let mut feed = Vec::new();
-        feed.extend_from_slice(&Command::LineSpacing(0).as_bytes());
-
-        // Each row will contain the information of 8 rows from the picture
-        let mut printer_rows: Vec<Vec<u8>> = Vec::new();
-
+    /// Scales the image to its printed width and converts it to black (0)
+    /// and white (255) dots of the image mode. The height is derived from
+    /// the dot size so the image keeps its aspect ratio on paper.
+    fn bitmap(&self, printer_width: usize) -> image::GrayImage {
         let (im_width, im_height) = self.img.dimensions();
-        // We redefine the aspect ratio
-        let aspect_ratio = (im_width as f64) / (im_height as f64);
-
-        let sc_width = (im_width as f64) * self.opts.scale;
-        let sc_height = ((sc_width) / aspect_ratio).floor() as u32;
-        let sc_width = sc_width.floor() as u32;
-        let x_offset = match justification {
-            Justification::Left => 0,
-            Justification::Center => (im_width - sc_width) / 2,
-            Justification::Right => im_width - sc_width,
-        };
+        let width = self.opts.width.dots(im_width, printer_width as u32) as f64 * self.opts.scale;
+        let height = width * im_height as f64 / im_width.max(1) as f64;
 
-        let mut composite = image::GrayImage::from_pixel(im_width, sc_height, [255].into());
-        image::imageops::overlay(
-            &mut composite,
-            &image::imageops::resize(&self.img, sc_width, sc_height, self.opts.filter_type),
-            x_offset,
-            0,
-        );
-        let mut img = image::imageops::crop(&mut composite, 0, 0, im_width, sc_height).to_image();
-
-        // Multiplied by 3 to account for the reduced vertical density
-        let new_height =
-            ((printer_width as f64 * self.opts.scale) / (aspect_ratio * 3.0)).floor() as u32;
-
-        img = image::imageops::resize(
-            &img,
-            printer_width as u32,
-            new_height,
-            self.opts.filter_type,
-        );
-        img = match self.opts.bit_map_algorithm {
-            BitMapAlgorithm::Dithering => {
-                image::imageops::dither(&mut img, &image::imageops::BiLevel);
-                img
-            }
-            BitMapAlgorithm::Threshold(threshold) => image::GrayImage::from_raw(
-                img.width(),
-                img.height(),
-                img.into_raw()
-                    .into_iter()
-                    .map(|intensity| if intensity > threshold { 255 } else { 0 })
-                    .collect(),
-            )
-            .unwrap(),
-        };
+        let (dot_width, dot_height) = self.opts.mode.dot_size();
+        let new_width = ((width / dot_width as f64).floor() as u32).max(1);
+        let new_height = ((height / dot_height as f64).round() as u32).max(1);
 
-        // We will turn the image into a grayscale boolean matrix
-        for (y, pixel_row) in img.enumerate_rows() {
-            // Here we iterate over each row of the image.
-            if y % 8 == 0 {
-                printer_rows.push(vec![0; printer_width as usize]);
-            }
-            let row = printer_rows.get_mut((y / 8) as usize).unwrap();
-            // Here, we iterate horizontally this time
-            for (x, y, pixel) in pixel_row {
-                let ps = pixel.channels();
-                // We get the color as a boolean
-                let mut color = if ps[0] == 0 { 0x01 } else { 0x00 };
-                // We shift the boolean by 7 - y%8 positions in the register
-                color <<= 7 - y % 8;
-                // An or operation preserves the previous pixels in the rows
-                row[x as usize] |= color;
-            }
+        let mut img =
+            image::imageops::resize(&self.img, new_width, new_height, self.opts.filter_type);
+        self.opts.tone.apply(&mut img);
+        self.opts.bit_map_algorithm.apply(&mut img);
+        img
+    }
+
+    /// Bytes printing the image, only as wide as the image itself. It is
+    /// placed on the line by the printer's justification.
+    pub fn as_bytes(&self, printer_width: usize, line_spacing: Option<u8>) -> Vec<u8> {
+        let img = self.bitmap(printer_width);
+        match self.opts.mode {
+            ImageMode::Column8 => Self::column_bytes(&img, 1, line_spacing),
+            ImageMode::Column24 => Self::column_bytes(&img, 3, line_spacing),
+            ImageMode::Raster(scale) => self.raster_bytes(&img, scale),
         }
+    }
 
-        // Finally, we push each row to the feed vector
-        for (_idx, printer_row) in printer_rows.iter().enumerate() {
+    /// Sends the image as strips of dot columns with ESC *, each column
+    /// made of `column_bytes` bytes
+    fn column_bytes(
+        img: &image::GrayImage,
+        column_bytes: u32,
+        line_spacing: Option<u8>,
+    ) -> Vec<u8> {
+        let mut feed = Vec::new();
+        feed.extend_from_slice(&Command::LineSpacing(0).as_bytes());
+
+        let (width, height) = img.dimensions();
+        let strip_height = column_bytes * 8;
+        let m = if column_bytes == 1 { 0x01 } else { 0x21 };
+        for strip in 0..height.div_ceil(strip_height) {
             // We first, declare a bitmap mode
             feed.extend_from_slice(&Command::Bitmap.as_bytes());
-            // Now, we pass m
-            let m = 0x01;
             feed.push(m);
             // The formula on how many pixels we will do, is nL + nH * 256
-            feed.push((printer_width % 256) as u8); // nL
-            feed.push((printer_width / 256) as u8); // nH
-            feed.extend_from_slice(printer_row);
+            feed.push((width % 256) as u8); // nL
+            feed.push((width / 256) as u8); // nH
+            for x in 0..width {
+                for byte in 0..column_bytes {
+                    let top = strip * strip_height + byte * 8;
+                    feed.push(dots(img, (0..8).map(|bit| (x, top + bit))));
+                }
+            }
             feed.push(b'\n'); // Line feed and print
         }
 
@@ -158,4 +266,49 @@ impl EscposImage {
 
         feed
     }
+
+    /// Sends the image as bands of raster rows with GS v 0
+    fn raster_bytes(&self, img: &image::GrayImage, scale: RasterScale) -> Vec<u8> {
+        let mut feed = Vec::new();
+        let (width, height) = img.dimensions();
+        let width_bytes = width.div_ceil(8);
+        let mut top = 0;
+        while top < height {
+            let band_height = self.opts.band_height.min(height - top);
+            feed.extend_from_slice(&[0x1d, 0x76, 0x30, scale as u8]);
+            feed.extend_from_slice(&(width_bytes as u16).to_le_bytes());
+            feed.extend_from_slice(&(band_height as u16).to_le_bytes());
+            feed.extend(raster_rows(img, top..top + band_height));
+            top += band_height;
+        }
+        feed
+    }
+
+    /// Converts the image to raster rows, returns its width and height in
+    /// dots along with the rows
+    pub(crate) fn raster(&self, printer_width: usize) -> (u32, u32, Vec<u8>) {
+        let img = self.bitmap(printer_width);
+        let (width, height) = img.dimensions();
+        (width, height, raster_rows(&img, 0..height).collect())
+    }
+}
+
+/// Packs the pixels of the rows, padded to whole bytes
+fn raster_rows(
+    img: &image::GrayImage,
+    rows: std::ops::Range<u32>,
+) -> impl Iterator<Item = u8> + '_ {
+    rows.flat_map(move |y| {
+        (0..img.width().div_ceil(8))
+            .map(move |byte| dots(img, (0..8).map(|bit| (byte * 8 + bit, y))))
+    })
+}
+
+/// Packs 8 pixels into a byte, most significant bit first, with black
+/// pixels set. Pixels outside of the image are left white.
+fn dots(img: &image::GrayImage, pixels: impl Iterator<Item = (u32, u32)>) -> u8 {
+    pixels.fold(0, |byte, (x, y)| {
+        let black = x < img.width() && y < img.height() && img.get_pixel(x, y)[0] == 0;
+        byte << 1 | black as u8
+    })
 }
@@ -1,3 +1,9 @@
+mod barcode;
+mod dither;
 mod escpos_image;
+mod qr_code;
 
-pub use escpos_image::{BitMapAlgorithm, EscposImage, ImageOptions};
+pub use barcode::{Barcode, BarcodeSystem, Code128Set};
+pub use dither::BitMapAlgorithm;
+pub use escpos_image::{EscposImage, ImageMode, ImageOptions, ImageWidth, RasterScale};
+pub use qr_code::{QrCode, QrCodeOptions};
@@ -0,0 +1,148 @@
+use crate::command::{Command, QrErrorCorrection, QrModel};
+use crate::error::{Error, Result};
+
+const MODULE_SIZE_MIN: u8 = 1;
+const MODULE_SIZE_MAX: u8 = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QrCodeOptions {
+    model: QrModel,
+    module_size: u8,
+    error_correction: QrErrorCorrection,
+}
+
+impl QrCodeOptions {
+    pub fn model(&mut self, model: QrModel) -> &mut Self {
+        self.model = model;
+        self
+    }
+    pub fn module_size(&mut self, module_size: u8) -> Result<&mut Self> {
+        if !(MODULE_SIZE_MIN..=MODULE_SIZE_MAX).contains(&module_size) {
+            Err(Error::InvalidQrModuleSize)
+        } else {
+            self.module_size = module_size;
+            Ok(self)
+        }
+    }
+    pub fn error_correction(&mut self, error_correction: QrErrorCorrection) -> &mut Self {
+        self.error_correction = error_correction;
+        self
+    }
+}
+
+impl Default for QrCodeOptions {
+    fn default() -> Self {
+        Self {
+            model: QrModel::Model2,
+            module_size: 6,
+            error_correction: QrErrorCorrection::M,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum QrDataMode {
+    Numeric,
+    Alphanumeric,
+    Byte,
+}
+
+impl QrDataMode {
+    fn detect(data: &[u8]) -> Self {
+        if data.iter().all(u8::is_ascii_digit) {
+            Self::Numeric
+        } else if data.iter().all(|byte| {
+            byte.is_ascii_digit() || byte.is_ascii_uppercase() || b" $%*+-./:".contains(byte)
+        }) {
+            Self::Alphanumeric
+        } else {
+            Self::Byte
+        }
+    }
+}
+
+/// Maximum number of characters of the largest symbol version for the model
+fn capacity(model: QrModel, error_correction: QrErrorCorrection, mode: QrDataMode) -> usize {
+    use QrDataMode::*;
+    use QrErrorCorrection::*;
+    let table = match (model, mode) {
+        // version 14
+        (QrModel::Model1, Numeric) => [1167, 919, 667, 509],
+        (QrModel::Model1, Alphanumeric) => [707, 557, 404, 310],
+        (QrModel::Model1, Byte) => [486, 382, 276, 211],
+        // version 40
+        (QrModel::Model2, Numeric) => [7089, 5596, 3993, 3057],
+        (QrModel::Model2, Alphanumeric) => [4296, 3391, 2420, 1852],
+        (QrModel::Model2, Byte) => [2953, 2331, 1663, 1273],
+    };
+    match error_correction {
+        L => table[0],
+        M => table[1],
+        Q => table[2],
+        H => table[3],
+    }
+}
+
+/// A QR code which fits into a symbol of the configured model
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QrCode {
+    data: Vec<u8>,
+    opts: QrCodeOptions,
+}
+
+impl QrCode {
+    pub fn new(data: impl AsRef<[u8]>, opts: &QrCodeOptions) -> Result<Self> {
+        let data = data.as_ref();
+        let max = capacity(opts.model, opts.error_correction, QrDataMode::detect(data));
+        if data.is_empty() || data.len() > max {
+            return Err(Error::InvalidQrCodeLength(data.len(), max));
+        }
+        Ok(Self {
+            data: data.to_vec(),
+            opts: opts.clone(),
+        })
+    }
+
+    pub fn as_bytes(&self) -> Vec<u8> {
+        let mut feed = Vec::new();
+        feed.extend_from_slice(&Command::QrModel(self.opts.model).as_bytes());
+        feed.extend_from_slice(&Command::QrModuleSize(self.opts.module_size).as_bytes());
+        feed.extend_from_slice(&Command::QrErrorCorrection(self.opts.error_correction).as_bytes());
+
+        // Store the data in the symbol storage area, pL + pH * 256 = len + 3
+        let len = self.data.len() + 3;
+        feed.extend_from_slice(&[0x1d, 0x28, 0x6b, (len % 256) as u8, (len / 256) as u8]);
+        feed.extend_from_slice(&[0x31, 0x50, 0x30]);
+        feed.extend_from_slice(&self.data);
+
+        feed.extend_from_slice(&Command::QrPrint.as_bytes());
+        feed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn capacity_limits() -> Result<()> {
+        let mut opts = QrCodeOptions::default();
+        opts.error_correction(QrErrorCorrection::H);
+        assert!(QrCode::new(vec![b'a'; 1273], &opts).is_ok());
+        assert!(matches!(
+            QrCode::new(vec![b'a'; 1274], &opts),
+            Err(Error::InvalidQrCodeLength(1274, 1273))
+        ));
+        assert!(QrCode::new(vec![b'1'; 3057], &opts).is_ok());
+        assert!(QrCode::new("", &opts).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn store_data() -> Result<()> {
+        let bytes = QrCode::new("AB", &QrCodeOptions::default())?.as_bytes();
+        let store = [0x1d, 0x28, 0x6b, 0x05, 0x00, 0x31, 0x50, 0x30, b'A', b'B'];
+        assert!(bytes.windows(store.len()).any(|window| window == store));
+        Ok(())
+    }
+}
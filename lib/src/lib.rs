@@ -5,18 +5,35 @@ extern crate lazy_static;
 
 pub extern crate pulldown_cmark;
 
+mod bitmap_font;
 pub mod command;
 pub mod config;
+pub mod decoder;
+pub mod device;
+mod directive;
+pub mod encoding;
 mod error;
+#[cfg(feature = "bitmap-fallback")]
+mod glyph_fallback;
+pub mod graphics;
 pub mod instruction;
 mod markdown;
+pub mod preview;
 mod printer;
 mod pulldown_cmark_ext;
+pub mod resolver;
 mod split_words;
+pub mod status;
 pub mod style;
+mod table;
 
 pub use config::PrinterConfig;
+pub use directive::CodeBlockDirective;
 pub use error::{Error, Result};
-pub use markdown::MarkdownRenderOptions;
-pub use printer::{Printer, PrinterDevice};
+pub use markdown::{
+    FootnoteOptions, FootnotePlacement, MarkdownRenderOptions, MarkdownWarning, MissingImage,
+    Strictness, TaskListOptions,
+};
+pub use printer::{BidirectionalDevice, Printer, PrinterDevice};
 pub use pulldown_cmark::{Options as MarkdownParserOptions, Parser as MarkdownParser};
+pub use table::{TableBorder, TableOptions};
@@ -1,14 +1,152 @@
+use crate::directive::{default_directives, CodeBlockDirective, DirectiveBuffer};
+use crate::encoding::EncodingPolicy;
 use crate::error::{Error, Result};
 use crate::instruction::{EscposImage, ImageOptions};
 use crate::printer::{Printer, PrinterDevice};
 use crate::pulldown_cmark_ext::{EventExt, TagExt};
+use crate::resolver::{default_resolver, ImageResolver};
 use crate::style::{StyleSheet, StyleTag};
-use pulldown_cmark::{Event, Tag};
+use crate::table::{TableBuffer, TableOptions};
+use pulldown_cmark::{CodeBlockKind, Event, Tag};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::io;
+use std::ops::Range;
+use std::sync::Arc;
 
-#[derive(Debug, Clone, Default)]
+/// How Markdown which cannot be printed is handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strictness {
+    /// Rendering fails at the first unsupported event or tag
+    #[default]
+    Strict,
+    /// Unsupported events are skipped or printed as plain text, and the
+    /// contents of unsupported tags are printed without their style. Each
+    /// of them is reported as a [`MarkdownWarning`].
+    Lenient,
+}
+
+/// Markdown which was skipped or simplified in [`Strictness::Lenient`] mode
+#[derive(Debug)]
+pub struct MarkdownWarning {
+    /// Byte range of the source, if the events were passed with offsets
+    pub offset: Option<Range<usize>>,
+    /// The error strict rendering would have failed with
+    pub error: Error,
+}
+
+impl fmt::Display for MarkdownWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.offset {
+            Some(offset) => write!(f, "{} at {}..{}", self.error, offset.start, offset.end),
+            None => write!(f, "{}", self.error),
+        }
+    }
+}
+
+/// What is printed for images which could not be resolved
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MissingImage {
+    /// Rendering fails with the error of the resolver
+    #[default]
+    Error,
+    /// The URL is printed in brackets instead of images which were not
+    /// found, images which failed to load still fail rendering
+    Placeholder,
+}
+
+/// Where footnote definitions are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FootnotePlacement {
+    /// Collected and printed at the end of the document
+    #[default]
+    End,
+    /// Printed where they appear in the document
+    Inline,
+}
+
+#[derive(Debug, Clone)]
+pub struct FootnoteOptions {
+    /// Text printed for references in the `footnoteref` style, `{num}` is
+    /// replaced by the number of the footnote and `{label}` by its label
+    pub reference: String,
+    pub placement: FootnotePlacement,
+}
+
+impl Default for FootnoteOptions {
+    fn default() -> Self {
+        Self {
+            reference: "[{num}]".to_string(),
+            placement: FootnotePlacement::default(),
+        }
+    }
+}
+
+impl FootnoteOptions {
+    fn reference(&self, num: u64, label: &str) -> String {
+        self.reference
+            .replace("{num}", &num.to_string())
+            .replace("{label}", label)
+    }
+}
+
+/// Glyphs printed for the checkboxes of task lists, e.g. `"■ "` and `"□ "`
+/// if the printer's code page has box characters
+#[derive(Debug, Clone)]
+pub struct TaskListOptions {
+    pub checked: String,
+    pub unchecked: String,
+}
+
+impl Default for TaskListOptions {
+    fn default() -> Self {
+        Self {
+            checked: "[x] ".to_string(),
+            unchecked: "[ ] ".to_string(),
+        }
+    }
+}
+
+impl TaskListOptions {
+    fn marker(&self, checked: bool) -> &str {
+        if checked {
+            &self.checked
+        } else {
+            &self.unchecked
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct MarkdownRenderOptions {
     pub styles: StyleSheet,
     pub image: ImageOptions,
+    /// Loads the images, by default from `data:` URIs and files relative to
+    /// the current working directory
+    pub image_resolver: Arc<dyn ImageResolver>,
+    pub missing_image: MissingImage,
+    pub strictness: Strictness,
+    pub table: TableOptions,
+    pub footnotes: FootnoteOptions,
+    pub tasks: TaskListOptions,
+    /// Fenced code blocks printed as symbols, keyed by the first word of the info string
+    pub directives: HashMap<String, CodeBlockDirective>,
+}
+
+impl Default for MarkdownRenderOptions {
+    fn default() -> Self {
+        Self {
+            styles: StyleSheet::default(),
+            image: ImageOptions::default(),
+            image_resolver: default_resolver(),
+            missing_image: MissingImage::default(),
+            strictness: Strictness::default(),
+            table: TableOptions::default(),
+            footnotes: FootnoteOptions::default(),
+            tasks: TaskListOptions::default(),
+            directives: default_directives(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -29,6 +167,10 @@ impl TagState {
 #[derive(Default)]
 struct RendererState<'a> {
     tree: Vec<(Tag<'a>, TagState)>,
+    table: Option<TableBuffer>,
+    directive: Option<DirectiveBuffer>,
+    /// Numbers of the footnotes in the order they are first referenced
+    footnotes: HashMap<String, u64>,
 }
 
 impl<'a> RendererState<'a> {
@@ -48,6 +190,7 @@ impl<'a> RendererState<'a> {
                     }
                 }
             }
+            Tag::FootnoteDefinition(ref label) => TagState::Item(self.footnote_num(label)),
             _ => TagState::Stateless,
         };
         self.tree.push((tag, state));
@@ -63,6 +206,11 @@ impl<'a> RendererState<'a> {
         }
     }
 
+    fn footnote_num(&mut self, label: &str) -> u64 {
+        let next = self.footnotes.len() as u64 + 1;
+        *self.footnotes.entry(label.to_string()).or_insert(next)
+    }
+
     fn tag(&self) -> Option<&Tag<'a>> {
         self.tree.last().map(|item| &item.0)
     }
@@ -76,7 +224,130 @@ impl<'a> RendererState<'a> {
     }
 
     fn style_tags(&self) -> Result<Vec<StyleTag>> {
-        self.tree.iter().map(|(tag, _)| tag.style_tag()).collect()
+        let mut parent: Option<&Tag> = None;
+        self.tree
+            .iter()
+            .map(|(tag, _)| {
+                let style_tag = match (tag, parent) {
+                    (Tag::TableCell, Some(Tag::TableHead)) => StyleTag::Th,
+                    (tag, _) => tag.style_tag()?,
+                };
+                parent = Some(tag);
+                Ok(style_tag)
+            })
+            .collect()
+    }
+
+    /// Collects the event into the current table, if there is one. Returns the
+    /// event back if it still has to be rendered.
+    fn buffer_table_event(
+        &mut self,
+        event: Event<'a>,
+        footnotes: &FootnoteOptions,
+    ) -> Result<Option<Event<'a>>> {
+        let table = match self.table.as_mut() {
+            Some(table) => table,
+            None => return Ok(Some(event)),
+        };
+        match event {
+            Event::End(Tag::Table(..)) => return Ok(Some(event)),
+            Event::Start(tag) => {
+                match tag {
+                    Tag::TableHead => table.start_row(true),
+                    Tag::TableRow => table.start_row(false),
+                    Tag::TableCell => table.start_cell(),
+                    _ => {}
+                }
+                self.push_tag(tag)?;
+            }
+            Event::End(tag) => self.pop_tag(&tag)?,
+            Event::Text(text) | Event::Code(text) => table.push_str(&text),
+            Event::SoftBreak | Event::HardBreak => table.push_str(" "),
+            Event::FootnoteReference(label) => {
+                let num = self.footnote_num(&label);
+                if let Some(table) = self.table.as_mut() {
+                    table.push_str(&footnotes.reference(num, &label));
+                }
+            }
+            _ => {}
+        }
+        Ok(None)
+    }
+}
+
+/// An event with its offset in the source, if known
+type OffsetEvent<'a> = (Event<'a>, Option<Range<usize>>);
+
+/// Events of the document, with footnote definitions moved to the end if
+/// they are printed there. Moved definitions are sorted by the order of
+/// their first reference, unreferenced ones follow in source order.
+struct EventQueue<'a, I> {
+    iter: I,
+    /// Labels in the order they are first referenced
+    references: Vec<String>,
+    definitions: Vec<(String, Vec<OffsetEvent<'a>>)>,
+    footnotes: VecDeque<OffsetEvent<'a>>,
+    in_footnote: bool,
+    defer_footnotes: bool,
+}
+
+impl<'a, I> EventQueue<'a, I> {
+    fn new(iter: I, defer_footnotes: bool) -> Self {
+        Self {
+            iter,
+            references: Vec::new(),
+            definitions: Vec::new(),
+            footnotes: VecDeque::new(),
+            in_footnote: false,
+            defer_footnotes,
+        }
+    }
+}
+
+impl<'a, I> Iterator for EventQueue<'a, I>
+where
+    I: Iterator<Item = OffsetEvent<'a>>,
+{
+    type Item = OffsetEvent<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (event, offset) in self.iter.by_ref() {
+            if self.defer_footnotes {
+                match &event {
+                    Event::Start(Tag::FootnoteDefinition(label)) => {
+                        self.definitions.push((label.to_string(), Vec::new()));
+                        self.in_footnote = true;
+                    }
+                    Event::FootnoteReference(label)
+                        if !self.in_footnote && !self.references.iter().any(|r| r == &**label) =>
+                    {
+                        self.references.push(label.to_string());
+                    }
+                    _ => {}
+                }
+                if self.in_footnote {
+                    self.in_footnote = !matches!(event, Event::End(Tag::FootnoteDefinition(_)));
+                    if let Some((_, events)) = self.definitions.last_mut() {
+                        events.push((event, offset));
+                    }
+                    continue;
+                }
+            }
+            return Some((event, offset));
+        }
+        if !self.definitions.is_empty() {
+            let mut definitions = std::mem::take(&mut self.definitions);
+            let references = &self.references;
+            definitions.sort_by_key(|(label, _)| {
+                references
+                    .iter()
+                    .position(|r| r == label)
+                    .unwrap_or(usize::MAX)
+            });
+            self.footnotes
+                .extend(definitions.into_iter().flat_map(|(_, events)| events));
+        }
+        self.footnotes.pop_front()
     }
 }
 
@@ -88,21 +359,124 @@ where
     where
         I: Iterator<Item = Event<'a>>,
     {
+        self.render_markdown(iter.map(|event| (event, None)), opts)?;
+        Ok(self)
+    }
+
+    /// Renders events along with their source offsets, e.g. from
+    /// [`MarkdownParser::into_offset_iter`](crate::MarkdownParser::into_offset_iter),
+    /// and returns the warnings of [`Strictness::Lenient`] mode
+    pub fn markdown_with_offsets<'a, I>(
+        &mut self,
+        iter: I,
+        opts: &MarkdownRenderOptions,
+    ) -> Result<Vec<MarkdownWarning>>
+    where
+        I: Iterator<Item = (Event<'a>, Range<usize>)>,
+    {
+        self.render_markdown(iter.map(|(event, offset)| (event, Some(offset))), opts)
+    }
+
+    /// Renders the events without printing anything and returns everything
+    /// which is not supported, so problems are found before a receipt is
+    /// printed halfway. Characters which cannot be encoded are returned
+    /// without an offset, whatever the encoding policy. Other errors, e.g.
+    /// missing images, are returned as is.
+    pub fn validate_markdown<'a, I>(
+        &self,
+        iter: I,
+        opts: &MarkdownRenderOptions,
+    ) -> Result<Vec<MarkdownWarning>>
+    where
+        I: Iterator<Item = (Event<'a>, Range<usize>)>,
+    {
+        let mut config = self.config.clone();
+        if config.encoding_policy == EncodingPolicy::Strict {
+            config.encoding_policy(EncodingPolicy::Replace);
+        }
+        let mut printer = Printer {
+            device: io::sink(),
+            config,
+            state: self.state.clone(),
+            substitutions: Vec::new(),
+        };
+        let opts = MarkdownRenderOptions {
+            strictness: Strictness::Lenient,
+            ..opts.clone()
+        };
+        let mut warnings = printer.markdown_with_offsets(iter, &opts)?;
+        warnings.extend(printer.substitutions.iter().map(|sub| MarkdownWarning {
+            offset: None,
+            error: Error::UnencodableChar(sub.original),
+        }));
+        Ok(warnings)
+    }
+
+    fn render_markdown<'a, I>(
+        &mut self,
+        iter: I,
+        opts: &MarkdownRenderOptions,
+    ) -> Result<Vec<MarkdownWarning>>
+    where
+        I: Iterator<Item = (Event<'a>, Option<Range<usize>>)>,
+    {
+        let lenient = opts.strictness == Strictness::Lenient;
+        let mut warnings = Vec::new();
         let mut state = RendererState::default();
-        for event in iter {
+        let defer_footnotes = opts.footnotes.placement == FootnotePlacement::End;
+        let mut events = EventQueue::new(iter.fuse(), defer_footnotes).peekable();
+        let mut pending = None;
+        while let Some((event, offset)) = pending.take().or_else(|| events.next()) {
+            // the marker of a loose task list item is printed in its paragraph
+            let (event, offset) = match events.next_if(|(next, _)| {
+                matches!(event, Event::TaskListMarker(_))
+                    && matches!(next, Event::Start(Tag::Paragraph))
+            }) {
+                Some(paragraph) => {
+                    pending = Some((event, offset));
+                    paragraph
+                }
+                None => (event, offset),
+            };
+            let event = match state.buffer_table_event(event, &opts.footnotes)? {
+                Some(event) => event,
+                None => continue,
+            };
             match event {
+                // the contents of unsupported tags are printed in the parent's style
+                Event::Start(tag) | Event::End(tag) if lenient && tag.style_tag().is_err() => {
+                    if let Err(error) = tag.style_tag() {
+                        warnings.push(MarkdownWarning { offset, error });
+                    }
+                }
                 Event::Start(tag) => {
                     state.push_tag(tag.clone())?;
                     let style_tags = state.style_tags()?;
-                    let style = opts.styles.get(&style_tags);
+                    let mut style = opts.styles.get(&style_tags);
+                    // the task marker replaces the prefix of the item
+                    if matches!(events.peek(), Some((Event::TaskListMarker(_), _))) {
+                        style.prefix.clear();
+                    }
                     self.font_style(&style)?;
-                    self.begin_block_style(&style, state.tag_state())?;
+                    // directives print without the margins and prefix of code blocks
+                    if let Tag::CodeBlock(CodeBlockKind::Fenced(info)) = &tag {
+                        state.directive = DirectiveBuffer::from_info(info, &opts.directives);
+                    }
+                    if state.directive.is_none() {
+                        self.begin_block_style(&style, state.tag_state())?;
+                    }
 
                     match tag {
-                        Tag::Image(_, filename, _) => {
-                            let img = image::open(filename.as_ref())?;
-                            let escpos_img = EscposImage::new(&img, &opts.image);
-                            self.image(&escpos_img)?;
+                        Tag::Image(_, url, _) => {
+                            match (opts.image_resolver.resolve(&url), opts.missing_image) {
+                                (Ok(img), _) => {
+                                    self.image(&EscposImage::new(&img, &opts.image))?;
+                                }
+                                (Err(Error::ImageNotFound(_)), MissingImage::Placeholder) => {
+                                    self.println(format!("[{}]", url))?;
+                                }
+                                (Err(err), _) => return Err(err),
+                            }
 
                             let mut img_caption_tags = style_tags;
                             img_caption_tags.push(StyleTag::ImgCaption);
@@ -110,11 +484,15 @@ where
                             self.font_style(&img_caption_style)?;
                             self.begin_block_style(&img_caption_style, None)?;
                         }
+                        Tag::Table(alignments) => {
+                            state.table = Some(TableBuffer::new(alignments));
+                        }
                         _ => {}
                     }
                 }
                 Event::End(tag) => {
                     let style_tags = state.style_tags()?;
+                    let mut is_directive = false;
                     match tag {
                         Tag::Image(..) => {
                             let mut img_caption_tags = style_tags.clone();
@@ -122,16 +500,33 @@ where
                             let img_caption_style = opts.styles.get(&img_caption_tags);
                             self.end_block_style(&img_caption_style)?;
                         }
+                        Tag::Table(..) => {
+                            if let Some(table) = state.table.take() {
+                                self.table(&table, &style_tags, &opts.styles, &opts.table)?;
+                            }
+                        }
+                        Tag::CodeBlock(..) => {
+                            if let Some(directive) = state.directive.take() {
+                                self.directive(&directive)?;
+                                is_directive = true;
+                            }
+                        }
                         _ => {}
                     }
-                    let style = opts.styles.get(&style_tags);
-                    self.end_block_style(&style)?;
+                    if !is_directive {
+                        let style = opts.styles.get(&style_tags);
+                        self.end_block_style(&style)?;
+                    }
                     state.pop_tag(&tag)?;
                     let style = opts.styles.get(&state.style_tags()?);
                     self.font_style(&style)?;
                 }
                 Event::Text(text) => {
-                    self.print(text)?;
+                    if let Some(directive) = state.directive.as_mut() {
+                        directive.push_str(&text);
+                    } else {
+                        self.print(text)?;
+                    }
                 }
                 Event::Code(text) => {
                     let mut style_tags = state.style_tags()?;
@@ -146,6 +541,33 @@ where
                     let style = opts.styles.get(&state.style_tags()?);
                     self.font_style(&style)?;
                 }
+                Event::FootnoteReference(label) => {
+                    let num = state.footnote_num(&label);
+                    let mut style_tags = state.style_tags()?;
+                    style_tags.push(StyleTag::FootnoteRef);
+                    let style = opts.styles.get(&style_tags);
+                    self.font_style(&style)?;
+                    self.begin_block_style(&style, None)?;
+
+                    self.print(opts.footnotes.reference(num, &label))?;
+
+                    self.end_block_style(&style)?;
+                    let style = opts.styles.get(&state.style_tags()?);
+                    self.font_style(&style)?;
+                }
+                Event::TaskListMarker(checked) => {
+                    let mut style_tags = state.style_tags()?;
+                    style_tags.push(StyleTag::Task);
+                    let style = opts.styles.get(&style_tags);
+                    self.font_style(&style)?;
+                    self.begin_block_style(&style, None)?;
+
+                    self.print(opts.tasks.marker(checked))?;
+
+                    self.end_block_style(&style)?;
+                    let style = opts.styles.get(&state.style_tags()?);
+                    self.font_style(&style)?;
+                }
                 Event::SoftBreak => {
                     self.print(" ")?;
                 }
@@ -166,9 +588,193 @@ where
                     let style = opts.styles.get(&state.style_tags()?);
                     self.font_style(&style)?;
                 }
-                event => return Err(Error::MarkdownEventUnimplemented(event.to_static())),
+                event => {
+                    let error = Error::MarkdownEventUnimplemented(event.clone().to_static());
+                    if !lenient {
+                        return Err(error);
+                    }
+                    warnings.push(MarkdownWarning { offset, error });
+                }
             }
         }
-        Ok(self)
+        Ok(warnings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::Command;
+    use crate::decoder::{decode, Decoded};
+    use crate::preview::TextPreview;
+    use crate::{MarkdownParser, MarkdownParserOptions, PrinterConfig};
+
+    const MD: &str = "Hi <b>there</b>[^1]\n\n- [x] done\n\n[^1]: A note\n";
+
+    fn parser() -> MarkdownParser<'static> {
+        MarkdownParser::new_ext(
+            MD,
+            MarkdownParserOptions::ENABLE_FOOTNOTES | MarkdownParserOptions::ENABLE_TASKLISTS,
+        )
+    }
+
+    fn preview() -> Result<Printer<TextPreview>> {
+        let config = PrinterConfig::tm_t20ii();
+        config.clone().build(TextPreview::new(&config))
+    }
+
+    /// Renders the markdown and returns the non-empty lines of the preview
+    fn render_lines(parser: MarkdownParser, opts: &MarkdownRenderOptions) -> Result<Vec<String>> {
+        let mut printer = preview()?;
+        printer.markdown(parser, opts)?;
+        Ok(printer
+            .device()
+            .render()
+            .lines()
+            .map(|line| line.trim_end().to_string())
+            .filter(|line| !line.is_empty())
+            .collect())
+    }
+
+    #[test]
+    fn lenient() -> Result<()> {
+        let mut printer = preview()?;
+        let mut opts = MarkdownRenderOptions::default();
+        assert!(printer.markdown(parser(), &opts).is_err());
+
+        let warnings = printer.validate_markdown(parser().into_offset_iter(), &opts)?;
+        let offsets = warnings
+            .iter()
+            .map(|warning| &MD[warning.offset.clone().unwrap()])
+            .collect::<Vec<_>>();
+        assert_eq!(offsets, vec!["<b>", "</b>"]);
+
+        opts.strictness = Strictness::Lenient;
+        let mut printer = preview()?;
+        let warnings = printer.markdown_with_offsets(parser().into_offset_iter(), &opts)?;
+        assert_eq!(warnings.len(), 2);
+        let text = printer.device().render();
+        assert!(text.contains("Hi there[1]"));
+        assert!(text.contains("[x] done"));
+        assert!(text.contains("A note"));
+        Ok(())
+    }
+
+    #[test]
+    fn validate_encoding() -> Result<()> {
+        let md = "Snow ☃ and ☃";
+        let opts = MarkdownRenderOptions::default();
+        let config = PrinterConfig::tm_t20ii();
+        let mut printer = config.build(Vec::new())?;
+        let parser = MarkdownParser::new(md);
+        assert!(matches!(
+            printer.markdown(parser, &opts),
+            Err(Error::UnencodableChar('☃'))
+        ));
+
+        let warnings =
+            printer.validate_markdown(MarkdownParser::new(md).into_offset_iter(), &opts)?;
+        assert_eq!(warnings.len(), 2);
+        assert!(warnings
+            .iter()
+            .all(|warning| matches!(warning.error, Error::UnencodableChar('☃'))));
+
+        let mut config = PrinterConfig::tm_t20ii();
+        config.encoding_policy(EncodingPolicy::Replace);
+        let mut printer = config.build(Vec::new())?;
+        printer.markdown(MarkdownParser::new(md), &opts)?;
+        assert_eq!(printer.substitutions().len(), 2);
+        assert!(printer.reset()?.substitutions().is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn footnotes() -> Result<()> {
+        let md = "Terms[^t] apply[^r].\n\n[^u]: Unused.\n\n[^r]: Returns within 30 days.\n\n[^t]: See website.\n\nThanks!\n";
+        let render = |placement| -> Result<Vec<String>> {
+            let mut opts = MarkdownRenderOptions::default();
+            opts.footnotes.placement = placement;
+            let parser = MarkdownParser::new_ext(md, MarkdownParserOptions::ENABLE_FOOTNOTES);
+            render_lines(parser, &opts)
+        };
+        assert_eq!(
+            render(FootnotePlacement::End)?,
+            vec![
+                "Terms[1] apply[2].",
+                "Thanks!",
+                "[1] See website.",
+                "[2] Returns within 30 days.",
+                "[3] Unused."
+            ]
+        );
+        assert_eq!(
+            render(FootnotePlacement::Inline)?,
+            vec![
+                "Terms[1] apply[2].",
+                "[3] Unused.",
+                "[2] Returns within 30 days.",
+                "[1] See website.",
+                "Thanks!"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn directive_block_style() -> Result<()> {
+        let md = "```barcode\n12345\n```\n\n```\ncode\n```\n";
+        let mut printer = PrinterConfig::tm_t20ii().build(Vec::new())?;
+        let parser = MarkdownParser::new(md);
+        printer.markdown(parser, &MarkdownRenderOptions::default())?;
+        let decoded = decode(printer.device());
+        let barcode = decoded
+            .iter()
+            .position(|item| matches!(item, Decoded::Barcode { .. }))
+            .unwrap();
+        let margins = decoded
+            .iter()
+            .enumerate()
+            .filter(|(_, item)| **item == Decoded::Command(Command::FeedPaper(80)))
+            .map(|(idx, _)| idx)
+            .collect::<Vec<_>>();
+        // only the plain code block gets the top margin of the code block style
+        assert_eq!(margins.len(), 1);
+        assert!(margins[0] > barcode);
+        Ok(())
+    }
+
+    #[test]
+    fn missing_image() -> Result<()> {
+        let opts = MarkdownRenderOptions {
+            missing_image: MissingImage::Placeholder,
+            ..Default::default()
+        };
+        let mut printer = preview()?;
+        printer.markdown(MarkdownParser::new("![logo](missing.png)"), &opts)?;
+        assert!(printer.device().render().contains("[missing.png]"));
+        // only images which were not found are replaced
+        let res = printer.markdown(MarkdownParser::new("![logo](data:image/png,abc)"), &opts);
+        assert!(matches!(res, Err(Error::InvalidDataUri(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn task_list() -> Result<()> {
+        let render = |md| -> Result<Vec<String>> {
+            let mut opts = MarkdownRenderOptions::default();
+            opts.tasks.checked = "(v) ".to_string();
+            let parser = MarkdownParser::new_ext(md, MarkdownParserOptions::ENABLE_TASKLISTS);
+            render_lines(parser, &opts)
+        };
+        assert_eq!(
+            render("- [x] Chop onions\n- [ ] Prep salad\n- Plate\n")?,
+            vec!["(v) Chop onions", "[ ] Prep salad", "* Plate"]
+        );
+        // the items of loose lists are paragraphs
+        assert_eq!(
+            render("- [x] Chop onions\n\n- [ ] Prep salad\n")?,
+            vec!["(v) Chop onions", "[ ] Prep salad"]
+        );
+        Ok(())
     }
 }
@@ -0,0 +1,163 @@
+//! Virtual printers which render the bytes sent by a
+//! [`Printer`](crate::Printer) instead of printing them on paper.
+
+mod raster;
+mod text;
+
+pub use raster::RasterPreview;
+pub use text::TextPreview;
+
+use crate::command::{
+    CharMagnification, CodeTable, Command, CutFunction, Font, UnderlineThickness,
+};
+use crate::config::PrinterConfig;
+use crate::decoder::{Bitmap, RasterImage};
+
+/// Line spacing selected by ESC 2
+const DEFAULT_LINE_SPACING: usize = 30;
+
+/// Paper fed before cutting, `None` if the paper is not cut
+fn cut_feed(function: CutFunction) -> Option<usize> {
+    match function {
+        CutFunction::Cut => Some(0),
+        CutFunction::FeedAndCut(n) | CutFunction::FeedToCutPosition(n) => Some(n as usize),
+        CutFunction::SetCutPosition(_) => None,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct TextAttrs {
+    font: Font,
+    magnification: CharMagnification,
+    char_spacing: u8,
+    bold: bool,
+    double_strike: bool,
+    underline: UnderlineThickness,
+    reverse: bool,
+    code_table: CodeTable,
+    double_byte: bool,
+    double_byte_spacing: (u8, u8),
+    double_byte_quadruple: bool,
+}
+
+impl TextAttrs {
+    /// Applies a character formatting command, returns `false` for other commands
+    fn apply(&mut self, cmd: Command) -> bool {
+        match cmd {
+            Command::PrintModeDefault => {
+                self.font = Font::FontA;
+                self.bold = false;
+                self.underline = UnderlineThickness::Off;
+                self.magnification = CharMagnification::default();
+            }
+            Command::Font(font) => self.font = font,
+            Command::Underline(thickness) => self.underline = thickness,
+            Command::Bold(enabled) => self.bold = enabled,
+            Command::DoubleStrike(enabled) => self.double_strike = enabled,
+            Command::WhiteBlackReverse(enabled) => self.reverse = enabled,
+            Command::CharSize(magnification) => self.magnification = magnification,
+            Command::CharSpacing(spacing) => self.char_spacing = spacing,
+            Command::CodeTable(table) => self.code_table = table,
+            Command::DoubleByteMode(enabled) => self.double_byte = enabled,
+            Command::DoubleByteSpacing(left, right) => self.double_byte_spacing = (left, right),
+            Command::DoubleByteQuadruple(enabled) => self.double_byte_quadruple = enabled,
+            _ => return false,
+        }
+        true
+    }
+
+    /// Decodes the character at the start of the text, returns it along with
+    /// the number of bytes it takes
+    fn decode(&self, config: &PrinterConfig, bytes: &[u8]) -> (char, usize) {
+        match config.double_byte {
+            Some(encoding) if self.double_byte && bytes[0] >= 0x80 => {
+                encoding.decode(bytes).unwrap_or(('?', bytes.len().min(2)))
+            }
+            _ => (self.code_table.decode(bytes[0]).unwrap_or('?'), 1),
+        }
+    }
+
+    /// Whether the character was printed with the double-byte font
+    fn is_full_width(&self, ch: char) -> bool {
+        self.double_byte && !ch.is_ascii()
+    }
+
+    /// Dots covered by the character
+    fn char_width(&self, config: &PrinterConfig, ch: char) -> usize {
+        let width = if self.is_full_width(ch) {
+            let (left, right) = self.double_byte_spacing;
+            let quadruple = if self.double_byte_quadruple { 2 } else { 1 };
+            (config.double_byte_width + left as usize + right as usize) * quadruple
+        } else {
+            config.font_widths.get(&self.font) + self.char_spacing as usize
+        };
+        width * self.magnification.width() as usize
+    }
+}
+
+/// A rendered character or bit image
+struct Cell {
+    width: usize,
+    height: usize,
+    pixels: Vec<bool>,
+}
+
+impl Cell {
+    fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![false; width * height],
+        }
+    }
+
+    fn from_bitmap(bitmap: &Bitmap) -> Self {
+        // (dot width, dot height, bytes per column)
+        let (dot_w, dot_h, rows) = match bitmap.mode {
+            0 => (2, 3, 1),
+            1 => (1, 3, 1),
+            32 => (2, 1, 3),
+            _ => (1, 1, 3),
+        };
+        let mut cell = Cell::new(bitmap.width * dot_w, 24);
+        for (col, bytes) in bitmap.data.chunks(rows).enumerate() {
+            for (row, byte) in bytes.iter().enumerate() {
+                for bit in 0..8 {
+                    if byte & (0x80 >> bit) != 0 {
+                        let y = (row * 8 + bit) * dot_h;
+                        cell.fill(col * dot_w, y, dot_w, dot_h);
+                    }
+                }
+            }
+        }
+        cell
+    }
+
+    fn from_raster(raster: &RasterImage) -> Self {
+        let (dot_w, dot_h) = raster.scale.dot_size();
+        let (dot_w, dot_h) = (dot_w as usize, dot_h as usize);
+        let mut cell = Cell::new(raster.width_bytes * 8 * dot_w, raster.height * dot_h);
+        for (y, row) in raster.data.chunks(raster.width_bytes).enumerate() {
+            for (byte_x, byte) in row.iter().enumerate() {
+                for bit in 0..8 {
+                    if byte & (0x80 >> bit) != 0 {
+                        cell.fill((byte_x * 8 + bit) * dot_w, y * dot_h, dot_w, dot_h);
+                    }
+                }
+            }
+        }
+        cell
+    }
+
+    fn get(&self, x: usize, y: usize) -> bool {
+        self.pixels[y * self.width + x]
+    }
+
+    fn fill(&mut self, x: usize, y: usize, width: usize, height: usize) {
+        for py in y..(y + height).min(self.height) {
+            for px in x..(x + width).min(self.width) {
+                self.pixels[py * self.width + px] = true;
+            }
+        }
+    }
+}
@@ -0,0 +1,367 @@
+use super::{cut_feed, Cell, TextAttrs, DEFAULT_LINE_SPACING};
+use crate::bitmap_font::BitmapFont;
+use crate::command::{Command, Justification, UnderlineThickness};
+use crate::config::PrinterConfig;
+use crate::decoder::{Decoded, Decoder};
+use std::io;
+
+const WHITE: u8 = 255;
+const BLACK: u8 = 0;
+
+/// Directions of the lines of a box-drawing character
+fn box_drawing_lines(ch: char) -> Option<[bool; 4]> {
+    // left, right, up, down
+    Some(match ch {
+        '─' | '═' => [true, true, false, false],
+        '│' | '║' => [false, false, true, true],
+        '┼' | '╬' => [true, true, true, true],
+        '┌' | '╔' => [false, true, false, true],
+        '┐' | '╗' => [true, false, false, true],
+        '└' | '╚' => [false, true, true, false],
+        '┘' | '╝' => [true, false, true, false],
+        '├' | '╠' => [false, true, true, true],
+        '┤' | '╣' => [true, false, true, true],
+        '┬' | '╦' => [true, true, false, true],
+        '┴' | '╩' => [true, true, true, false],
+        _ => return None,
+    })
+}
+
+struct Renderer<'a> {
+    config: &'a PrinterConfig,
+    canvas: Vec<u8>,
+    y: usize,
+    attrs: TextAttrs,
+    line_spacing: Option<u8>,
+    left_margin: usize,
+    justification: Justification,
+    line: Vec<Cell>,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(config: &'a PrinterConfig) -> Self {
+        Self {
+            config,
+            canvas: Vec::new(),
+            y: 0,
+            attrs: TextAttrs::default(),
+            line_spacing: None,
+            left_margin: 0,
+            justification: Justification::default(),
+            line: Vec::new(),
+        }
+    }
+
+    fn line_spacing(&self) -> usize {
+        self.line_spacing
+            .map(|spacing| spacing as usize)
+            .unwrap_or(DEFAULT_LINE_SPACING)
+    }
+
+    fn printable_width(&self) -> usize {
+        self.config.width - self.left_margin.min(self.config.width)
+    }
+
+    fn ensure_height(&mut self, height: usize) {
+        let len = height * self.config.width;
+        if self.canvas.len() < len {
+            self.canvas.resize(len, WHITE);
+        }
+    }
+
+    /// Prints the line buffer and advances by at least `min_feed` dots
+    fn print_line(&mut self, min_feed: usize) {
+        let line = std::mem::take(&mut self.line);
+        let height = line.iter().map(|cell| cell.height).max().unwrap_or(0);
+        let line_width: usize = line.iter().map(|cell| cell.width).sum();
+        let free = self.printable_width().saturating_sub(line_width);
+        let mut x = self.left_margin
+            + match self.justification {
+                Justification::Left => 0,
+                Justification::Center => free / 2,
+                Justification::Right => free,
+            };
+        self.ensure_height(self.y + height);
+        for cell in line {
+            let top = self.y + height - cell.height;
+            for cy in 0..cell.height {
+                for cx in 0..cell.width {
+                    if cell.pixels[cy * cell.width + cx] && x + cx < self.config.width {
+                        self.canvas[(top + cy) * self.config.width + x + cx] = BLACK;
+                    }
+                }
+            }
+            x += cell.width;
+        }
+        self.y += height.max(min_feed);
+    }
+
+    fn push_cell(&mut self, cell: Cell) {
+        let line_width: usize = self.line.iter().map(|cell| cell.width).sum();
+        if !self.line.is_empty() && line_width + cell.width > self.printable_width() {
+            self.print_line(self.line_spacing());
+        }
+        self.line.push(cell);
+    }
+
+    fn char_cell(&self, ch: char) -> Cell {
+        let attrs = self.attrs;
+        let font = BitmapFont::for_font(attrs.font);
+        let full_width = attrs.is_full_width(ch);
+        let quadruple = if full_width && attrs.double_byte_quadruple {
+            2
+        } else {
+            1
+        };
+        let (mag_x, mag_y) = (
+            attrs.magnification.width() as usize * quadruple,
+            attrs.magnification.height() as usize * quadruple,
+        );
+        // fonts are about twice as high as they are wide, e.g. 12x24 and 9x17,
+        // double-byte fonts are square
+        let (char_width, char_height) = if full_width {
+            (self.config.double_byte_width, self.config.double_byte_width)
+        } else {
+            let width = self.config.font_widths.get(&attrs.font);
+            (width, width * 2)
+        };
+        let mut cell = Cell::new(attrs.char_width(self.config, ch), char_height * mag_y);
+
+        if let Some([left, right, up, down]) = box_drawing_lines(ch) {
+            let (cx, cy) = (char_width / 2 * mag_x, char_height / 2 * mag_y);
+            let (thick_x, thick_y) = (mag_x.max(2), mag_y.max(2));
+            if left {
+                cell.fill(0, cy, cx + thick_x, thick_y);
+            }
+            if right {
+                cell.fill(cx, cy, cell.width, thick_y);
+            }
+            if up {
+                cell.fill(cx, 0, thick_x, cy + thick_y);
+            }
+            if down {
+                cell.fill(cx, cy, thick_x, cell.height);
+            }
+        } else if font.has_glyph(ch) {
+            let ox = char_width.saturating_sub(font.width) / 2;
+            let oy = char_height.saturating_sub(font.height) / 2;
+            let emphasized = attrs.bold || attrs.double_strike;
+            for gy in 0..font.height {
+                for gx in 0..font.width {
+                    let set = font.pixel(ch, gx, gy)
+                        || (emphasized && gx > 0 && font.pixel(ch, gx - 1, gy));
+                    if set {
+                        cell.fill((ox + gx) * mag_x, (oy + gy) * mag_y, mag_x, mag_y);
+                    }
+                }
+            }
+        } else if !ch.is_whitespace() {
+            // hollow box for characters without a glyph
+            let (w, h) = (
+                char_width.saturating_sub(2).max(1) * mag_x,
+                char_height.saturating_sub(4).max(1) * mag_y,
+            );
+            let (x, y) = (mag_x, 2 * mag_y);
+            cell.fill(x, y, w, mag_y);
+            cell.fill(x, y + h - mag_y, w, mag_y);
+            cell.fill(x, y, mag_x, h);
+            cell.fill(x + w - mag_x, y, mag_x, h);
+        }
+
+        let underline = match attrs.underline {
+            UnderlineThickness::Off => 0,
+            UnderlineThickness::OneDot => 1,
+            UnderlineThickness::TwoDot => 2,
+        };
+        if underline != 0 {
+            let thickness = underline * mag_y;
+            cell.fill(0, cell.height - thickness, cell.width, thickness);
+        }
+        if attrs.reverse {
+            cell.pixels.iter_mut().for_each(|pixel| *pixel = !*pixel);
+        }
+        cell
+    }
+
+    fn text(&mut self, bytes: &[u8]) {
+        let mut idx = 0;
+        while idx < bytes.len() {
+            match bytes[idx] {
+                b'\n' => self.print_line(self.line_spacing()),
+                0x00..=0x1f => {}
+                _ => {
+                    let (ch, len) = self.attrs.decode(self.config, &bytes[idx..]);
+                    let cell = self.char_cell(ch);
+                    self.push_cell(cell);
+                    idx += len;
+                    continue;
+                }
+            }
+            idx += 1;
+        }
+    }
+
+    fn cut(&mut self, feed: usize) {
+        if !self.line.is_empty() {
+            self.print_line(self.line_spacing());
+        }
+        self.y += feed;
+        self.ensure_height(self.y + 1);
+        let start = self.y * self.config.width;
+        for (x, pixel) in self.canvas[start..start + self.config.width]
+            .iter_mut()
+            .enumerate()
+        {
+            if x % 8 < 4 {
+                *pixel = BLACK;
+            }
+        }
+        self.y += 1;
+    }
+
+    fn command(&mut self, cmd: Command) {
+        if self.attrs.apply(cmd) {
+            return;
+        }
+        match cmd {
+            Command::Init => {
+                self.attrs = TextAttrs::default();
+                self.line_spacing = None;
+                self.left_margin = 0;
+                self.justification = Justification::default();
+                self.line.clear();
+            }
+            Command::LineSpacing(spacing) => self.line_spacing = Some(spacing),
+            Command::DefaultLineSpacing => self.line_spacing = None,
+            Command::LeftMargin(margin) => self.left_margin = margin as usize,
+            Command::Justification(justification) => self.justification = justification,
+            Command::FeedPaper(units) => self.print_line(units as usize),
+            Command::FeedLines(lines) => {
+                let feed = lines as usize * self.line_spacing();
+                if self.line.is_empty() {
+                    self.y += feed;
+                } else {
+                    self.print_line(feed);
+                }
+            }
+            Command::Cut(_, function) => {
+                if let Some(feed) = cut_feed(function) {
+                    self.cut(feed)
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) -> image::GrayImage {
+        if !self.line.is_empty() {
+            self.print_line(self.line_spacing());
+        }
+        let height = self.y.max(1);
+        self.ensure_height(height);
+        self.canvas.truncate(height * self.config.width);
+        image::GrayImage::from_raw(self.config.width as u32, height as u32, self.canvas)
+            .expect("canvas matches the image dimensions")
+    }
+}
+
+/// A virtual printer which records the bytes sent by a
+/// [`Printer`](crate::Printer) and renders them to an image of the paper
+///
+/// Text is drawn with built-in bitmap fonts approximating the printer fonts,
+/// barcodes and QR codes are not drawn.
+#[derive(Debug, Clone)]
+pub struct RasterPreview {
+    config: PrinterConfig,
+    bytes: Vec<u8>,
+}
+
+impl RasterPreview {
+    pub fn new(config: &PrinterConfig) -> Self {
+        Self {
+            config: config.clone(),
+            bytes: Vec::new(),
+        }
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+    }
+
+    /// Renders the received bytes to an image as wide as the configured paper
+    pub fn render(&self) -> image::GrayImage {
+        let mut renderer = Renderer::new(&self.config);
+        for item in Decoder::new(&self.bytes) {
+            match item {
+                Decoded::Command(cmd) => renderer.command(cmd),
+                Decoded::Text(bytes) => renderer.text(&bytes),
+                Decoded::Bitmap(bitmap) => {
+                    renderer.push_cell(Cell::from_bitmap(&bitmap));
+                }
+                Decoded::Raster(raster) => {
+                    // raster images are printed on their own right away
+                    if !renderer.line.is_empty() {
+                        renderer.print_line(renderer.line_spacing());
+                    }
+                    renderer.push_cell(Cell::from_raster(&raster));
+                    renderer.print_line(0);
+                }
+                _ => {}
+            }
+        }
+        renderer.finish()
+    }
+}
+
+impl io::Write for RasterPreview {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bytes.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::Font;
+    use crate::error::Result;
+    use crate::instruction::{
+        BitMapAlgorithm, EscposImage, ImageMode, ImageOptions, ImageWidth, RasterScale,
+    };
+    use image::{DynamicImage, GrayImage, Luma};
+
+    #[test]
+    fn text_and_image() -> Result<()> {
+        let config = PrinterConfig::tm_t20ii();
+        let mut printer = config.clone().build(RasterPreview::new(&config))?;
+        let mut opts = ImageOptions::default();
+        opts.width(ImageWidth::Native)
+            .bit_map_algorithm(BitMapAlgorithm::Threshold(128))
+            .mode(ImageMode::Raster(RasterScale::Normal));
+        let img = DynamicImage::ImageLuma8(GrayImage::from_pixel(64, 32, Luma([0])));
+        printer
+            .println("Hi")?
+            .image(&EscposImage::new(&img, &opts))?;
+
+        let preview = printer.device().render();
+        // a line of text followed by the image
+        assert_eq!(preview.width() as usize, config.width);
+        assert_eq!(preview.height() as usize, DEFAULT_LINE_SPACING + 32);
+        let is_black = |x: u32, y: u32| preview.get_pixel(x, y)[0] == BLACK;
+        let text_width = 2 * (config.font_widths.get(&Font::FontA) + config.char_spacing);
+        assert!((0..24).any(|y| (0..text_width as u32).any(|x| is_black(x, y))));
+        assert!((0..24).all(|y| (text_width as u32..config.width as u32).all(|x| !is_black(x, y))));
+        let image_top = DEFAULT_LINE_SPACING as u32;
+        assert!((image_top..image_top + 32).all(|y| (0..64).all(|x| is_black(x, y))));
+        assert!((image_top..image_top + 32).all(|y| !is_black(64, y)));
+        Ok(())
+    }
+}
@@ -0,0 +1,393 @@
+use super::{cut_feed, Cell, TextAttrs, DEFAULT_LINE_SPACING};
+use crate::command::{Command, Font, Justification, UnderlineThickness};
+use crate::config::PrinterConfig;
+use crate::decoder::{Decoded, Decoder};
+use std::io;
+
+/// Item waiting in the line buffer
+enum Item {
+    Char(char, TextAttrs),
+    Image(Cell),
+}
+
+struct Renderer<'a> {
+    config: &'a PrinterConfig,
+    /// Number of characters of font A fitting on the paper
+    columns: usize,
+    /// Dots covered by a single column
+    column_width: usize,
+    attrs: TextAttrs,
+    line_spacing: Option<u8>,
+    left_margin: usize,
+    justification: Justification,
+    line: Vec<(Item, usize)>,
+    /// Pixel rows of consecutive image lines, drawn together with half blocks
+    image: Vec<Vec<bool>>,
+    rows: Vec<Vec<(char, TextAttrs)>>,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(config: &'a PrinterConfig) -> Self {
+        let char_width = config.font_widths.get(&Font::FontA) + config.char_spacing;
+        let columns = (config.width / char_width.max(1)).max(1);
+        Self {
+            config,
+            columns,
+            column_width: (config.width / columns).max(1),
+            attrs: TextAttrs::default(),
+            line_spacing: None,
+            left_margin: 0,
+            justification: Justification::default(),
+            line: Vec::new(),
+            image: Vec::new(),
+            rows: Vec::new(),
+        }
+    }
+
+    fn line_spacing(&self) -> usize {
+        self.line_spacing
+            .map(|spacing| spacing as usize)
+            .unwrap_or(DEFAULT_LINE_SPACING)
+    }
+
+    fn printable_width(&self) -> usize {
+        self.config.width - self.left_margin.min(self.config.width)
+    }
+
+    /// Number of text rows for a paper feed of `dots`
+    fn feed_rows(&self, dots: usize) -> usize {
+        let spacing = match self.line_spacing() {
+            0 => DEFAULT_LINE_SPACING,
+            spacing => spacing,
+        };
+        (dots + spacing / 2) / spacing
+    }
+
+    fn blank_rows(&mut self, count: usize) {
+        self.flush_image();
+        self.rows.extend((0..count).map(|_| Vec::new()));
+    }
+
+    /// Offset from the left margin of a line leaving `free` space
+    fn justify(&self, free: usize) -> usize {
+        match self.justification {
+            Justification::Left => 0,
+            Justification::Center => free / 2,
+            Justification::Right => free,
+        }
+    }
+
+    /// Prints the line buffer and advances by at least `min_feed` dots
+    fn print_line(&mut self, min_feed: usize) {
+        let line = std::mem::take(&mut self.line);
+        if line.iter().any(|(item, _)| matches!(item, Item::Image(_))) {
+            self.print_image_line(line, min_feed);
+            return;
+        }
+
+        self.flush_image();
+        let mut row = Vec::new();
+        for (item, _) in line {
+            if let Item::Char(ch, attrs) = item {
+                row.push((ch, attrs));
+                // magnified characters take the width of several columns
+                let padding = attrs.magnification.width() as usize - 1;
+                row.extend((0..padding).map(|_| (' ', attrs)));
+            }
+        }
+        let margin = self.left_margin / self.column_width;
+        let free = self
+            .columns
+            .saturating_sub(margin)
+            .saturating_sub(row.len());
+        let offset = margin + self.justify(free);
+        row.splice(0..0, (0..offset).map(|_| (' ', TextAttrs::default())));
+        self.rows.push(row);
+        let feed = self.feed_rows(min_feed).saturating_sub(1);
+        self.blank_rows(feed);
+    }
+
+    /// Draws a line containing bit images into the pending image, text
+    /// printed on the same line is left out
+    fn print_image_line(&mut self, line: Vec<(Item, usize)>, min_feed: usize) {
+        let height = line
+            .iter()
+            .map(|(item, _)| match item {
+                Item::Image(cell) => cell.height,
+                Item::Char(..) => 0,
+            })
+            .max()
+            .unwrap_or(0);
+        let line_width: usize = line.iter().map(|(_, width)| width).sum();
+        let free = self.printable_width().saturating_sub(line_width);
+        let mut x = self.left_margin + self.justify(free);
+        let mut band = vec![vec![false; self.config.width]; height.max(min_feed)];
+        for (item, width) in line {
+            if let Item::Image(cell) = item {
+                let top = height - cell.height;
+                for cy in 0..cell.height {
+                    for cx in 0..cell.width.min(self.config.width.saturating_sub(x)) {
+                        band[top + cy][x + cx] = cell.get(cx, cy);
+                    }
+                }
+            }
+            x += width;
+        }
+        self.image.extend(band);
+    }
+
+    /// Draws the pending image with half block characters, each covering a
+    /// square of dots as wide as a column
+    fn flush_image(&mut self) {
+        if self.image.is_empty() {
+            return;
+        }
+        let image = std::mem::take(&mut self.image);
+        let size = self.column_width;
+        let block = |bx: usize, by: usize| {
+            let rows = image.iter().skip(by * size).take(size);
+            let dots = rows
+                .flat_map(|row| row.iter().skip(bx * size).take(size))
+                .filter(|dot| **dot)
+                .count();
+            dots * 2 >= size * size
+        };
+        let blocks_y = image.len().div_ceil(size);
+        for by in (0..blocks_y).step_by(2) {
+            let row = (0..self.columns)
+                .map(|bx| {
+                    let ch = match (block(bx, by), block(bx, by + 1)) {
+                        (true, true) => '█',
+                        (true, false) => '▀',
+                        (false, true) => '▄',
+                        (false, false) => ' ',
+                    };
+                    (ch, TextAttrs::default())
+                })
+                .collect();
+            self.rows.push(row);
+        }
+    }
+
+    fn push(&mut self, item: Item) {
+        let width = match &item {
+            Item::Char(ch, attrs) => attrs.char_width(self.config, *ch),
+            Item::Image(cell) => cell.width,
+        };
+        let line_width: usize = self.line.iter().map(|(_, width)| width).sum();
+        if !self.line.is_empty() && line_width + width > self.printable_width() {
+            self.print_line(self.line_spacing());
+        }
+        self.line.push((item, width));
+    }
+
+    fn text(&mut self, bytes: &[u8]) {
+        let mut idx = 0;
+        while idx < bytes.len() {
+            match bytes[idx] {
+                b'\n' => self.print_line(self.line_spacing()),
+                0x00..=0x1f => {}
+                _ => {
+                    let (ch, len) = self.attrs.decode(self.config, &bytes[idx..]);
+                    self.push(Item::Char(ch, self.attrs));
+                    idx += len;
+                    continue;
+                }
+            }
+            idx += 1;
+        }
+    }
+
+    fn feed(&mut self, dots: usize) {
+        if self.line.is_empty() {
+            let rows = self.feed_rows(dots);
+            self.blank_rows(rows);
+        } else {
+            self.print_line(dots);
+        }
+    }
+
+    fn cut(&mut self, feed: usize) {
+        self.feed(feed);
+        let row = "- "
+            .chars()
+            .cycle()
+            .take(self.columns)
+            .map(|ch| (ch, TextAttrs::default()))
+            .collect();
+        self.rows.push(row);
+    }
+
+    fn command(&mut self, cmd: Command) {
+        if self.attrs.apply(cmd) {
+            return;
+        }
+        match cmd {
+            Command::Init => {
+                self.attrs = TextAttrs::default();
+                self.line_spacing = None;
+                self.left_margin = 0;
+                self.justification = Justification::default();
+                self.line.clear();
+            }
+            Command::LineSpacing(spacing) => self.line_spacing = Some(spacing),
+            Command::DefaultLineSpacing => self.line_spacing = None,
+            Command::LeftMargin(margin) => self.left_margin = margin as usize,
+            Command::Justification(justification) => self.justification = justification,
+            Command::FeedPaper(units) => self.feed(units as usize),
+            Command::FeedLines(lines) => self.feed(lines as usize * self.line_spacing()),
+            Command::Cut(_, function) => {
+                if let Some(feed) = cut_feed(function) {
+                    self.cut(feed)
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(mut self, ansi: bool) -> String {
+        if !self.line.is_empty() {
+            self.print_line(self.line_spacing());
+        }
+        self.flush_image();
+        let mut out = String::new();
+        for mut row in self.rows {
+            while matches!(row.last(), Some((' ', attrs)) if !attrs.reverse && attrs.underline == UnderlineThickness::Off)
+            {
+                row.pop();
+            }
+            let mut current = TextAttrs::default();
+            for (ch, attrs) in row {
+                if ansi && sgr(&attrs) != sgr(&current) {
+                    out.push_str("\x1b[0");
+                    out.push_str(&sgr(&attrs));
+                    out.push('m');
+                    current = attrs;
+                }
+                out.push(ch);
+            }
+            if ansi && !sgr(&current).is_empty() {
+                out.push_str("\x1b[0m");
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// ANSI select graphic rendition parameters for the attributes
+fn sgr(attrs: &TextAttrs) -> String {
+    let mut params = String::new();
+    if attrs.bold || attrs.double_strike {
+        params.push_str(";1");
+    }
+    if attrs.underline != UnderlineThickness::Off {
+        params.push_str(";4");
+    }
+    if attrs.reverse {
+        params.push_str(";7");
+    }
+    params
+}
+
+/// A virtual printer which records the bytes sent by a
+/// [`Printer`](crate::Printer) and renders them as text for a terminal
+///
+/// Lines are as wide as the number of font A characters fitting on the
+/// paper. Feeds become empty lines, cuts a dashed line and bit images are
+/// drawn with half block characters.
+#[derive(Debug, Clone)]
+pub struct TextPreview {
+    config: PrinterConfig,
+    bytes: Vec<u8>,
+}
+
+impl TextPreview {
+    pub fn new(config: &PrinterConfig) -> Self {
+        Self {
+            config: config.clone(),
+            bytes: Vec::new(),
+        }
+    }
+
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+    }
+
+    fn render_text(&self, ansi: bool) -> String {
+        let mut renderer = Renderer::new(&self.config);
+        for item in Decoder::new(&self.bytes) {
+            match item {
+                Decoded::Command(cmd) => renderer.command(cmd),
+                Decoded::Text(bytes) => renderer.text(&bytes),
+                Decoded::Bitmap(bitmap) => renderer.push(Item::Image(Cell::from_bitmap(&bitmap))),
+                Decoded::Raster(raster) => {
+                    // raster images are printed on their own right away
+                    if !renderer.line.is_empty() {
+                        renderer.print_line(renderer.line_spacing());
+                    }
+                    renderer.push(Item::Image(Cell::from_raster(&raster)));
+                    renderer.print_line(0);
+                }
+                _ => {}
+            }
+        }
+        renderer.finish(ansi)
+    }
+
+    /// Renders the received bytes as plain text
+    pub fn render(&self) -> String {
+        self.render_text(false)
+    }
+
+    /// Renders the received bytes as text with ANSI escape codes for bold,
+    /// underlined and reversed text
+    pub fn render_ansi(&self) -> String {
+        self.render_text(true)
+    }
+}
+
+impl io::Write for TextPreview {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.bytes.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Result;
+
+    #[test]
+    fn text_lines() -> Result<()> {
+        let config = PrinterConfig::tm_t20ii();
+        let mut printer = config.build(TextPreview::new(&config))?;
+        printer
+            .reset()?
+            .println("left")?
+            .command(&Command::Justification(Justification::Right))?
+            .command(&Command::Bold(true))?
+            .println("right")?
+            .cut()?;
+        let preview = printer.device();
+        let lines = preview
+            .render()
+            .lines()
+            .map(String::from)
+            .collect::<Vec<_>>();
+        assert_eq!(lines[0], "left");
+        assert_eq!(lines[1], format!("{:>41}", "right"));
+        assert!(lines.last().unwrap().starts_with("- - -"));
+        assert!(preview.render_ansi().contains("\x1b[0;1mright\x1b[0m"));
+        Ok(())
+    }
+}
@@ -1,15 +1,23 @@
 use crate::command::{
-    CharMagnification, Charset, CodeTable, Command, Font, Justification, UnderlineThickness,
+    CharMagnification, Charset, CodeTable, Command, CutFunction, CutType, DrawerPin, Font,
+    HriPosition, Justification, StatusBackMask, UnderlineThickness,
 };
 use crate::config::PrinterConfig;
+use crate::encoding::{encode, CharMode, EncodedChar, Substitution};
 use crate::error::{Error, Result};
-use crate::instruction::EscposImage;
+use crate::instruction::{Barcode, EscposImage, QrCode};
 use crate::split_words::split_words;
-use codepage_437::{IntoCp437, CP437_CONTROL};
 use std::io;
+use std::net::TcpStream;
+use std::time::Duration;
 
 pub trait PrinterDevice {
     fn write_all(&mut self, buf: &[u8]) -> io::Result<()>;
+
+    /// Sends bytes the device buffered to the printer
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
 }
 
 impl<T> PrinterDevice for T
@@ -19,6 +27,35 @@ where
     fn write_all(&mut self, buf: &[u8]) -> io::Result<()> {
         self.write_all(buf)
     }
+
+    fn flush(&mut self) -> io::Result<()> {
+        io::Write::flush(self)
+    }
+}
+
+/// A device which can also receive data from the printer
+pub trait BidirectionalDevice: PrinterDevice {
+    /// Reads bytes sent by the printer into `buf`, waiting at most `timeout`
+    /// for them to arrive. Returns `Ok(0)` if nothing arrived in time.
+    fn read_timeout(&mut self, buf: &mut [u8], timeout: Duration) -> io::Result<usize>;
+}
+
+impl BidirectionalDevice for TcpStream {
+    fn read_timeout(&mut self, buf: &mut [u8], timeout: Duration) -> io::Result<usize> {
+        // a zero timeout would block forever
+        self.set_read_timeout(Some(timeout.max(Duration::from_millis(1))))?;
+        match io::Read::read(self, buf) {
+            Err(err)
+                if matches!(
+                    err.kind(),
+                    io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                ) =>
+            {
+                Ok(0)
+            }
+            res => res,
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -31,6 +68,69 @@ pub struct PrinterState {
     pub(crate) left_margin: u16,
     pub(crate) justification: Justification,
     pub(crate) char_magnification: CharMagnification,
+    pub(crate) bold: bool,
+    pub(crate) underline: UnderlineThickness,
+    pub(crate) double_strike: bool,
+    pub(crate) white_black_reverse: bool,
+    pub(crate) code_table: CodeTable,
+    pub(crate) double_byte: bool,
+    pub(crate) double_byte_spacing: (u8, u8),
+    pub(crate) double_byte_quadruple: bool,
+    /// Whether the tracked attributes are known to be in effect on the
+    /// printer, which is only the case after an `Init`
+    pub(crate) synced: bool,
+}
+
+impl PrinterState {
+    fn new(char_spacing: u8) -> Self {
+        Self {
+            char_spacing,
+            line_spacing: None,
+            font: Font::default(),
+            left_offset: 0,
+            split_words: true,
+            left_margin: 0,
+            justification: Justification::default(),
+            char_magnification: CharMagnification::default(),
+            bold: false,
+            underline: UnderlineThickness::default(),
+            double_strike: false,
+            white_black_reverse: false,
+            code_table: CodeTable::default(),
+            double_byte: false,
+            double_byte_spacing: (0, 0),
+            double_byte_quadruple: false,
+            synced: false,
+        }
+    }
+
+    /// Returns whether sending the command would not change anything
+    fn is_in_effect(&self, cmd: &Command) -> bool {
+        if let Command::SplitWords(split) = cmd {
+            return self.split_words == *split;
+        }
+        self.synced
+            && match cmd {
+                Command::LineSpacing(units) => self.line_spacing == Some(*units),
+                Command::DefaultLineSpacing => self.line_spacing.is_none(),
+                Command::CharSpacing(units) => self.char_spacing == *units,
+                Command::CharSize(magnification) => self.char_magnification == *magnification,
+                Command::Font(font) => self.font == *font,
+                Command::LeftMargin(margin) => self.left_margin == *margin,
+                Command::Justification(justification) => self.justification == *justification,
+                Command::Bold(enabled) => self.bold == *enabled,
+                Command::Underline(thickness) => self.underline == *thickness,
+                Command::DoubleStrike(enabled) => self.double_strike == *enabled,
+                Command::WhiteBlackReverse(enabled) => self.white_black_reverse == *enabled,
+                Command::CodeTable(table) => self.code_table == *table,
+                Command::DoubleByteMode(enabled) => self.double_byte == *enabled,
+                Command::DoubleByteSpacing(left, right) => {
+                    self.double_byte_spacing == (*left, *right)
+                }
+                Command::DoubleByteQuadruple(enabled) => self.double_byte_quadruple == *enabled,
+                _ => false,
+            }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -38,6 +138,7 @@ pub struct Printer<D> {
     pub(crate) device: D,
     pub(crate) config: PrinterConfig,
     pub(crate) state: PrinterState,
+    pub(crate) substitutions: Vec<Substitution>,
 }
 
 impl<D> Printer<D> {
@@ -54,28 +155,55 @@ impl<D> Printer<D> {
     }
 
     pub fn new(device: D, config: PrinterConfig) -> Result<Self> {
-        let state = PrinterState {
-            char_spacing: Self::reduce_spacing_param(config.char_spacing)?,
-            line_spacing: None,
-            font: Font::default(),
-            left_offset: 0,
-            split_words: true,
-            left_margin: 0,
-            justification: Justification::default(),
-            char_magnification: CharMagnification::default(),
-        };
+        let state = PrinterState::new(Self::reduce_spacing_param(config.char_spacing)?);
         Ok(Printer {
             device,
             config,
             state,
+            substitutions: Vec::new(),
         })
     }
 
+    /// Characters which were not printed as written because the code table
+    /// cannot encode them, see [`EncodingPolicy`](crate::encoding::EncodingPolicy).
+    /// They are collected until taken or the printer is reset.
+    pub fn substitutions(&self) -> &[Substitution] {
+        &self.substitutions
+    }
+
+    pub fn take_substitutions(&mut self) -> Vec<Substitution> {
+        std::mem::take(&mut self.substitutions)
+    }
+
+    pub fn device(&self) -> &D {
+        &self.device
+    }
+
+    pub fn device_mut(&mut self) -> &mut D {
+        &mut self.device
+    }
+
+    pub fn into_device(self) -> D {
+        self.device
+    }
+
     pub(crate) fn calc_char_size(&self) -> usize {
         (self.config.font_widths.get(&self.state.font) + self.state.char_spacing as usize)
             * self.state.char_magnification.width() as usize
     }
 
+    pub(crate) fn calc_double_byte_size(&self) -> usize {
+        let (left, right) = self.state.double_byte_spacing;
+        let quadruple = if self.state.double_byte_quadruple {
+            2
+        } else {
+            1
+        };
+        (self.config.double_byte_width + left as usize + right as usize)
+            * self.state.char_magnification.width() as usize
+            * quadruple
+    }
+
     pub(crate) fn printable_width(&self) -> usize {
         self.config.width - (self.state.left_margin as usize).min(self.config.width)
     }
@@ -106,7 +234,6 @@ impl<D> Printer<D>
 where
     D: PrinterDevice,
 {
-    cmd_fn!(cut, Cut);
     cmd_fn!(init, Init);
     cmd_fn!(print_mode_default, PrintModeDefault);
     cmd_fn!(charset, Charset, charset, Charset);
@@ -120,9 +247,15 @@ where
     cmd_fn!(split_words, SplitWords, enabled, bool);
     cmd_fn!(left_margin, LeftMargin, margin, u16);
     cmd_fn!(justification, Justification, justification, Justification);
+    cmd_fn!(barcode_hri, BarcodeHri, position, HriPosition);
+    cmd_fn!(barcode_hri_font, BarcodeHriFont, font, Font);
+    cmd_fn!(auto_status_back, AutoStatusBack, mask, StatusBackMask);
+    cmd_fn!(double_byte_mode, DoubleByteMode, enabled, bool);
+    cmd_fn!(double_byte_quadruple, DoubleByteQuadruple, enabled, bool);
 
     pub fn reset(&mut self) -> Result<&mut Self> {
         self.state.split_words = true;
+        self.substitutions.clear();
         let og_char_spacing = self.config.char_spacing;
         self.init()?
             .print_mode_default()?
@@ -135,27 +268,88 @@ where
     }
 
     pub fn print(&mut self, text: impl ToString) -> Result<&mut Self> {
-        let mut content = text.to_string().into_cp437(&CP437_CONTROL)?;
+        let current = match self.config.double_byte {
+            Some(encoding) if self.state.double_byte => CharMode::DoubleByte(encoding),
+            _ => CharMode::SingleByte(self.state.code_table),
+        };
+        let content = encode(
+            &text.to_string(),
+            &self.config,
+            current,
+            &mut self.substitutions,
+        )?;
+        #[cfg(feature = "bitmap-fallback")]
+        let content = self.print_glyph_runs(content)?;
+        self.print_encoded(content)
+    }
 
+    /// Wraps and sends encoded text, switching code tables and double-byte
+    /// mode as needed
+    pub(crate) fn print_encoded(&mut self, mut content: Vec<EncodedChar>) -> Result<&mut Self> {
         let new_offset = if self.state.split_words {
             split_words(
                 &mut content,
                 self.state.left_offset,
                 self.printable_width(),
                 self.calc_char_size(),
+                self.calc_double_byte_size(),
             )
         } else {
-            (self.state.left_offset + content.len() * self.calc_char_size())
-                % self.printable_width()
+            let width = content
+                .iter()
+                .map(|ch| {
+                    if ch.is_full_width() {
+                        self.calc_double_byte_size()
+                    } else {
+                        self.calc_char_size()
+                    }
+                })
+                .sum::<usize>();
+            (self.state.left_offset + width) % self.printable_width()
         };
 
+        // double-byte mode is only kept on if it was on before
+        let double_byte = self.state.double_byte;
+        let mut bytes = Vec::with_capacity(content.len());
+        let mut mode = None;
+        for encoded in content {
+            let commands = match encoded.mode {
+                CharMode::SingleByte(table) => {
+                    vec![Command::DoubleByteMode(false), Command::CodeTable(table)]
+                }
+                CharMode::DoubleByte(_) => vec![Command::DoubleByteMode(true)],
+                _ => Vec::new(),
+            };
+            // the mode is only switched once per run of characters, even if
+            // the state of the printer is not known yet
+            if !commands.is_empty()
+                && mode.replace(encoded.mode) != Some(encoded.mode)
+                && commands.iter().any(|cmd| !self.state.is_in_effect(cmd))
+            {
+                unsafe {
+                    self.raw(&bytes)?;
+                }
+                bytes.clear();
+                for cmd in commands {
+                    self.command(&cmd)?;
+                }
+            }
+            bytes.extend(encoded.bytes);
+        }
         unsafe {
-            self.raw(content)?;
+            self.raw(bytes)?;
+        }
+        if !double_byte && self.state.double_byte {
+            self.double_byte_mode(false)?;
         }
         self.state.left_offset = new_offset;
         Ok(self)
     }
 
+    pub fn double_byte_spacing(&mut self, left: u8, right: u8) -> Result<&mut Self> {
+        self.command(&Command::DoubleByteSpacing(left, right))
+    }
+
     pub fn println(&mut self, text: impl ToString) -> Result<&mut Self> {
         self.print(text.to_string() + "\n")
     }
@@ -189,9 +383,64 @@ where
         self.command(&cmd)
     }
 
+    pub fn barcode_width(&mut self, width: u8) -> Result<&mut Self> {
+        if !(2..=6).contains(&width) {
+            return Err(Error::InvalidBarcodeWidth);
+        }
+        self.command(&Command::BarcodeWidth(width))
+    }
+
+    pub fn barcode_height(&mut self, height: u8) -> Result<&mut Self> {
+        if height == 0 {
+            return Err(Error::InvalidBarcodeHeight);
+        }
+        self.command(&Command::BarcodeHeight(height))
+    }
+
+    /// Feeds 0x96 units and cuts the paper
+    pub fn cut(&mut self) -> Result<&mut Self> {
+        self.cut_paper(CutType::Full, CutFunction::FeedAndCut(0x96))
+    }
+
+    /// Feeds 0x96 units and cuts the paper leaving a point uncut
+    pub fn partial_cut(&mut self) -> Result<&mut Self> {
+        self.cut_paper(CutType::Partial, CutFunction::FeedAndCut(0x96))
+    }
+
+    pub fn cut_paper(&mut self, cut_type: CutType, function: CutFunction) -> Result<&mut Self> {
+        self.command(&Command::Cut(cut_type, function))
+    }
+
+    /// Opens a cash drawer by pulsing `pin` on for `on` and off for `off`
+    /// times 2 ms
+    pub fn kick_drawer(&mut self, pin: DrawerPin, on: u8, off: u8) -> Result<&mut Self> {
+        self.command(&Command::DrawerKick(pin, on, off))
+    }
+
+    /// Opens a cash drawer right away by pulsing `pin` for `time` times 100 ms,
+    /// even while the printer is busy
+    pub fn pulse_drawer(&mut self, pin: DrawerPin, time: u8) -> Result<&mut Self> {
+        if !(1..=8).contains(&time) {
+            return Err(Error::InvalidDrawerPulseTime);
+        }
+        self.command(&Command::DrawerPulse(pin, time))
+    }
+
+    /// Beeps `times` times for `duration` times 50 ms
+    pub fn beep(&mut self, times: u8, duration: u8) -> Result<&mut Self> {
+        if !(1..=9).contains(&times) || !(1..=9).contains(&duration) {
+            return Err(Error::InvalidBeepParam);
+        }
+        self.command(&Command::Beep(times, duration))
+    }
+
+    /// Sends a command to the printer, unless its effect is already in place
     pub fn command(&mut self, cmd: &Command) -> Result<&mut Self> {
+        if self.state.is_in_effect(cmd) {
+            return Ok(self);
+        }
         unsafe {
-            self.raw(&cmd.as_bytes())?;
+            self.raw(cmd.as_bytes())?;
         }
         match cmd {
             Command::LineSpacing(units) => self.state.line_spacing = Some(*units),
@@ -202,12 +451,30 @@ where
             Command::SplitWords(split) => self.state.split_words = *split,
             Command::LeftMargin(margin) => self.state.left_margin = *margin,
             Command::Justification(justification) => self.state.justification = *justification,
+            Command::Bold(enabled) => self.state.bold = *enabled,
+            Command::Underline(thickness) => self.state.underline = *thickness,
+            Command::DoubleStrike(enabled) => self.state.double_strike = *enabled,
+            Command::WhiteBlackReverse(enabled) => self.state.white_black_reverse = *enabled,
+            Command::CodeTable(table) => self.state.code_table = *table,
+            Command::DoubleByteMode(enabled) => self.state.double_byte = *enabled,
+            Command::DoubleByteSpacing(left, right) => {
+                self.state.double_byte_spacing = (*left, *right)
+            }
+            Command::DoubleByteQuadruple(enabled) => self.state.double_byte_quadruple = *enabled,
+            Command::PrintModeDefault => {
+                self.state.font = Font::default();
+                self.state.bold = false;
+                self.state.underline = UnderlineThickness::default();
+                self.state.char_magnification = CharMagnification::default();
+            }
             Command::FeedPaper(_) | Command::FeedLines(_) => {
                 self.state.left_offset = 0;
             }
             Command::Init => {
-                self.state.char_magnification = CharMagnification::default();
-                self.state.font = Font::default();
+                let split_words = self.state.split_words;
+                self.state = PrinterState::new(0);
+                self.state.split_words = split_words;
+                self.state.synced = true;
             }
             _ => {} // do nothing
         }
@@ -216,18 +483,122 @@ where
 
     pub fn image(&mut self, image: &EscposImage) -> Result<&mut Self> {
         unsafe {
-            self.raw(image.as_bytes(
-                self.printable_width(),
-                self.state.justification,
-                self.state.line_spacing,
-            ))?;
+            self.raw(image.as_bytes(self.printable_width(), self.state.line_spacing))?;
         }
         self.state.left_offset = 0;
         Ok(self)
     }
 
+    pub fn barcode(&mut self, barcode: &Barcode) -> Result<&mut Self> {
+        // barcodes are only processed at the beginning of a line
+        if self.state.left_offset != 0 {
+            self.println("")?;
+        }
+        unsafe {
+            self.raw(barcode.as_bytes())?;
+        }
+        self.state.left_offset = 0;
+        Ok(self)
+    }
+
+    pub fn qr_code(&mut self, qr_code: &QrCode) -> Result<&mut Self> {
+        // symbols are only processed at the beginning of a line
+        if self.state.left_offset != 0 {
+            self.println("")?;
+        }
+        unsafe {
+            self.raw(qr_code.as_bytes())?;
+        }
+        self.state.left_offset = 0;
+        Ok(self)
+    }
+
+    /// Writes raw bytes to the device.
+    ///
+    /// # Safety
+    ///
+    /// The bytes are sent as is, so the tracked printer state may no longer
+    /// reflect the actual state of the printer afterwards.
     pub unsafe fn raw(&mut self, data: impl AsRef<[u8]>) -> Result<&mut Self> {
         self.device.write_all(data.as_ref())?;
         Ok(self)
     }
+
+    /// Sends the bytes buffered by the device, e.g. the job of a
+    /// [`NetworkDevice`](crate::device::NetworkDevice)
+    pub fn flush(&mut self) -> Result<&mut Self> {
+        self.device.flush()?;
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encoding::DoubleByteEncoding;
+
+    #[test]
+    fn skips_commands_in_effect() -> Result<()> {
+        let mut printer = PrinterConfig::default().build(Vec::new())?;
+        // the state of the printer is unknown until it is initialized
+        printer.bold(false)?.bold(false)?;
+        assert_eq!(printer.device, Command::Bold(false).as_bytes().repeat(2));
+
+        printer.device.clear();
+        printer
+            .init()?
+            .bold(false)?
+            .bold(true)?
+            .bold(true)?
+            .font(Font::FontA)?;
+        let expected = [Command::Init.as_bytes(), Command::Bold(true).as_bytes()].concat();
+        assert_eq!(printer.device, expected);
+
+        printer.device.clear();
+        printer.reset()?.bold(true)?;
+        assert!(printer.device.ends_with(&Command::Bold(true).as_bytes()));
+        Ok(())
+    }
+
+    #[test]
+    fn code_table_text() -> Result<()> {
+        let mut printer = PrinterConfig::default().build(Vec::new())?;
+        // the code table is selected before initializing, but only once
+        printer.print("éé")?;
+        let expected = [
+            &Command::DoubleByteMode(false).as_bytes()[..],
+            &Command::CodeTable(CodeTable::Pc437).as_bytes(),
+            b"\x82\x82",
+        ]
+        .concat();
+        assert_eq!(printer.device, expected);
+
+        printer.device.clear();
+        printer.init()?.print("é")?;
+        assert_eq!(
+            printer.device,
+            [&Command::Init.as_bytes()[..], b"\x82"].concat()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn double_byte_text() -> Result<()> {
+        let mut config = PrinterConfig::default();
+        config
+            .width(100)
+            .double_byte(Some(DoubleByteEncoding::ShiftJis));
+        let mut printer = config.build(Vec::new())?;
+        // four full-width characters fit on a line of 100 dots
+        printer.print("日本語です。")?;
+        let expected = [
+            &Command::DoubleByteMode(true).as_bytes()[..],
+            b"\x93\xfa\x96\x7b\x8c\xea\x82\xc5\n\x82\xb7\x81\x42",
+            &Command::DoubleByteMode(false).as_bytes(),
+        ]
+        .concat();
+        assert_eq!(printer.device, expected);
+        assert_eq!(printer.state.left_offset, 48);
+        Ok(())
+    }
 }
@@ -61,6 +61,11 @@ impl<'a> TagExt for Tag<'a> {
             Tag::Strikethrough => StyleTag::Strikethrough,
             Tag::Link(..) => StyleTag::A,
             Tag::Image(..) => StyleTag::Img,
+            Tag::Table(..) => StyleTag::Table,
+            Tag::TableHead => StyleTag::Thead,
+            Tag::TableRow => StyleTag::Tr,
+            Tag::TableCell => StyleTag::Td,
+            Tag::FootnoteDefinition(_) => StyleTag::Footnote,
             tag => return Err(Error::UnsupportedTag(tag.clone().to_static())),
         })
     }
@@ -0,0 +1,179 @@
+//! Loading of the images referenced by Markdown documents
+
+use crate::error::{Error, Result};
+use image::DynamicImage;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Turns the URL of a Markdown image into an image.
+///
+/// Resolvers return [`Error::ImageNotFound`] for URLs they do not handle so
+/// they can be combined with a [`ResolverChain`].
+pub trait ImageResolver: fmt::Debug + Send + Sync {
+    fn resolve(&self, url: &str) -> Result<DynamicImage>;
+}
+
+/// Loads images from files, relative paths are resolved against a base
+/// directory
+#[derive(Debug, Clone)]
+pub struct FileResolver {
+    base_dir: PathBuf,
+}
+
+impl FileResolver {
+    pub fn new(base_dir: impl Into<PathBuf>) -> Self {
+        Self {
+            base_dir: base_dir.into(),
+        }
+    }
+}
+
+impl Default for FileResolver {
+    /// Resolves paths against the current working directory
+    fn default() -> Self {
+        Self::new(".")
+    }
+}
+
+impl ImageResolver for FileResolver {
+    fn resolve(&self, url: &str) -> Result<DynamicImage> {
+        let path = match url.strip_prefix("file://") {
+            Some(path) => path,
+            // other schemes, e.g. http: or data:
+            None if url.split_once(':').is_some_and(|(scheme, _)| {
+                scheme.len() > 1 && scheme.chars().all(|c| c.is_ascii_alphanumeric())
+            }) =>
+            {
+                return Err(Error::ImageNotFound(url.to_string()))
+            }
+            None => url,
+        };
+        let path = self.base_dir.join(path);
+        if !path.is_file() {
+            return Err(Error::ImageNotFound(url.to_string()));
+        }
+        image::open(path).map_err(|err| Error::ImageLoad(url.to_string(), err))
+    }
+}
+
+/// Decodes `data:` URIs with a base64 payload, e.g.
+/// `data:image/png;base64,iVBORw0KGgo...`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DataUriResolver;
+
+impl ImageResolver for DataUriResolver {
+    fn resolve(&self, url: &str) -> Result<DynamicImage> {
+        let uri = url
+            .strip_prefix("data:")
+            .ok_or_else(|| Error::ImageNotFound(url.to_string()))?;
+        // the payload is too long to be useful in errors
+        let short_url = || url.chars().take(32).collect::<String>();
+        let invalid = || Error::InvalidDataUri(short_url());
+        let (media_type, data) = uri.split_once(',').ok_or_else(invalid)?;
+        if !media_type.ends_with(";base64") {
+            return Err(invalid());
+        }
+        let data = base64::decode(data.trim()).map_err(|_| invalid())?;
+        image::load_from_memory(&data).map_err(|err| Error::ImageLoad(short_url(), err))
+    }
+}
+
+/// Images registered under a name, e.g. assets embedded in the binary
+#[derive(Debug, Clone, Default)]
+pub struct MemoryResolver {
+    images: HashMap<String, DynamicImage>,
+}
+
+impl MemoryResolver {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, name: impl Into<String>, image: DynamicImage) -> &mut Self {
+        self.images.insert(name.into(), image);
+        self
+    }
+}
+
+impl ImageResolver for MemoryResolver {
+    fn resolve(&self, url: &str) -> Result<DynamicImage> {
+        self.images
+            .get(url)
+            .cloned()
+            .ok_or_else(|| Error::ImageNotFound(url.to_string()))
+    }
+}
+
+/// Tries resolvers in order until one finds the image
+#[derive(Debug, Clone, Default)]
+pub struct ResolverChain {
+    resolvers: Vec<Arc<dyn ImageResolver>>,
+}
+
+impl ResolverChain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, resolver: impl ImageResolver + 'static) -> &mut Self {
+        self.resolvers.push(Arc::new(resolver));
+        self
+    }
+}
+
+impl ImageResolver for ResolverChain {
+    fn resolve(&self, url: &str) -> Result<DynamicImage> {
+        for resolver in &self.resolvers {
+            match resolver.resolve(url) {
+                Err(Error::ImageNotFound(_)) => continue,
+                res => return res,
+            }
+        }
+        Err(Error::ImageNotFound(url.to_string()))
+    }
+}
+
+/// Resolves `data:` URIs and files relative to the current working directory
+pub fn default_resolver() -> Arc<dyn ImageResolver> {
+    let mut chain = ResolverChain::new();
+    chain.push(DataUriResolver).push(FileResolver::default());
+    Arc::new(chain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::GenericImageView;
+
+    // 1x1 PNG
+    const PNG: &str = "iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAAAAAA6fptVAAAACklEQVR4nGNgAAAAAgABSK+kcQAAAABJRU5ErkJggg==";
+
+    #[test]
+    fn resolve() {
+        let mut memory = MemoryResolver::new();
+        memory.insert("logo", DynamicImage::new_luma8(4, 2));
+        let mut chain = ResolverChain::new();
+        chain
+            .push(memory)
+            .push(DataUriResolver)
+            .push(FileResolver::new("does-not-exist"));
+
+        assert_eq!(chain.resolve("logo").unwrap().width(), 4);
+        let uri = format!("data:image/png;base64,{}", PNG);
+        assert_eq!(chain.resolve(&uri).unwrap().width(), 1);
+        assert!(matches!(
+            chain.resolve("data:image/png,abc"),
+            Err(Error::InvalidDataUri(_))
+        ));
+        assert!(matches!(
+            chain.resolve("missing.png"),
+            Err(Error::ImageNotFound(url)) if url == "missing.png"
+        ));
+        assert!(matches!(
+            FileResolver::default().resolve("https://example.com/logo.png"),
+            Err(Error::ImageNotFound(_))
+        ));
+    }
+}
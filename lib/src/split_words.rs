@@ -1,23 +1,30 @@
+use crate::encoding::EncodedChar;
+
 pub fn split_words(
-    content: &mut Vec<u8>,
+    content: &mut Vec<EncodedChar>,
     cur_offset: usize,
     printer_width: usize,
     char_size: usize,
+    full_width_size: usize,
 ) -> usize {
-    const WHITESPACE_CHARS: &[u8] = &[b'\n', b'\r', b' '];
+    const WHITESPACE_CHARS: &[u8] = b"\n\r ";
+    let is_whitespace = |ch: &EncodedChar| {
+        ch.as_ascii()
+            .is_some_and(|byte| WHITESPACE_CHARS.contains(&byte))
+    };
     let mut new_offset = cur_offset;
     let mut content_idx = 0;
     while content_idx < content.len() {
         // if newline continue
-        match content[content_idx] {
+        match content[content_idx].as_ascii() {
             // reset offset to beginning of line
-            b'\n' | b'\r' => {
+            Some(b'\n' | b'\r') => {
                 new_offset = 0;
                 content_idx += 1;
             }
             // continue or wrap line if width reached
-            b' ' => {
-                new_offset = new_offset + char_size;
+            Some(b' ') => {
+                new_offset += char_size;
                 if new_offset > printer_width {
                     content.remove(content_idx);
                     new_offset = 0;
@@ -27,21 +34,27 @@ pub fn split_words(
             }
             // split word or append if unnecessary
             _ => {
-                let next_whitespace = content[content_idx..]
+                // lines may be broken after every full-width character
+                let next_whitespace = if content[content_idx].is_full_width() {
+                    content_idx + 1
+                } else {
+                    content[content_idx..]
+                        .iter()
+                        .position(|ch| is_whitespace(ch) || ch.is_full_width())
+                        .map_or(content.len(), |idx| idx + content_idx)
+                };
+                let next_word_len = content[content_idx..next_whitespace]
                     .iter()
-                    .enumerate()
-                    .find_map(|(idx, ch)| {
-                        if WHITESPACE_CHARS.contains(ch) {
-                            Some(idx + content_idx)
+                    .map(|ch| {
+                        if ch.is_full_width() {
+                            full_width_size
                         } else {
-                            None
+                            char_size
                         }
                     })
-                    .unwrap_or_else(|| content.len());
-                let next_word = &content[content_idx..next_whitespace];
-                let next_word_len = next_word.len() * char_size;
+                    .sum::<usize>();
                 if next_word_len + new_offset > printer_width {
-                    content.insert(content_idx, b'\n');
+                    content.insert(content_idx, EncodedChar::ascii(b'\n'));
                     new_offset = next_word_len % printer_width;
                     content_idx = next_whitespace + 1;
                 } else {
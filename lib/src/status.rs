@@ -0,0 +1,528 @@
+//! Real-time status of the printer, requested with DLE EOT or reported
+//! with Automatic Status Back.
+
+use crate::command::{Command, StatusBackMask, StatusRequest};
+use crate::error::{Error, Result};
+use crate::printer::{BidirectionalDevice, Printer};
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Bits which are fixed in every status byte: bit 1 and 4 are set, bit 0
+/// and 7 are cleared
+const FIXED_MASK: u8 = 0b1001_0011;
+const FIXED_BITS: u8 = 0b0001_0010;
+
+fn bit(byte: u8, n: u8) -> bool {
+    byte & (1 << n) != 0
+}
+
+fn check(byte: u8) -> Result<u8> {
+    if byte & FIXED_MASK == FIXED_BITS {
+        Ok(byte)
+    } else {
+        Err(Error::InvalidStatus(byte))
+    }
+}
+
+/// Reply to [`StatusRequest::Printer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PrinterStatus {
+    /// Level of pin 3 of the drawer kick-out connector
+    pub drawer_pin_high: bool,
+    pub offline: bool,
+    /// Waiting for online recovery after an error
+    pub waiting_for_recovery: bool,
+    pub feed_button_pressed: bool,
+}
+
+impl PrinterStatus {
+    pub fn from_byte(byte: u8) -> Result<Self> {
+        let byte = check(byte)?;
+        Ok(Self {
+            drawer_pin_high: bit(byte, 2),
+            offline: bit(byte, 3),
+            waiting_for_recovery: bit(byte, 5),
+            feed_button_pressed: bit(byte, 6),
+        })
+    }
+}
+
+/// Reply to [`StatusRequest::Offline`], the causes of being offline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct OfflineStatus {
+    pub cover_open: bool,
+    /// Paper is being fed with the paper feed button
+    pub feed_button_feeding: bool,
+    /// Printing stopped because the paper ran out
+    pub paper_end_stop: bool,
+    pub error: bool,
+}
+
+impl OfflineStatus {
+    pub fn from_byte(byte: u8) -> Result<Self> {
+        let byte = check(byte)?;
+        Ok(Self {
+            cover_open: bit(byte, 2),
+            feed_button_feeding: bit(byte, 3),
+            paper_end_stop: bit(byte, 5),
+            error: bit(byte, 6),
+        })
+    }
+}
+
+/// Reply to [`StatusRequest::Error`], the kinds of errors which occurred
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ErrorStatus {
+    /// A mechanical error which can be recovered from, e.g. a paper jam
+    pub recoverable: bool,
+    pub autocutter: bool,
+    pub unrecoverable: bool,
+    /// An error which is recovered from automatically, e.g. an overheated head
+    pub auto_recoverable: bool,
+}
+
+impl ErrorStatus {
+    pub fn from_byte(byte: u8) -> Result<Self> {
+        let byte = check(byte)?;
+        Ok(Self {
+            recoverable: bit(byte, 2),
+            autocutter: bit(byte, 3),
+            unrecoverable: bit(byte, 5),
+            auto_recoverable: bit(byte, 6),
+        })
+    }
+}
+
+/// Reply to [`StatusRequest::Paper`], the state of the roll paper sensors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PaperStatus {
+    pub near_end: bool,
+    pub end: bool,
+}
+
+impl PaperStatus {
+    pub fn from_byte(byte: u8) -> Result<Self> {
+        let byte = check(byte)?;
+        Ok(Self {
+            near_end: bit(byte, 2) || bit(byte, 3),
+            end: bit(byte, 5) || bit(byte, 6),
+        })
+    }
+}
+
+/// The replies to all status requests
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Status {
+    pub printer: PrinterStatus,
+    pub offline: OfflineStatus,
+    pub error: ErrorStatus,
+    pub paper: PaperStatus,
+}
+
+impl Status {
+    /// Whether the printer is ready to print
+    pub fn is_ready(&self) -> bool {
+        !self.printer.offline && !self.paper.end
+    }
+}
+
+/// Status reported in an Automatic Status Back packet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct StatusBack {
+    /// Level of pin 3 of the drawer kick-out connector
+    pub drawer_pin_high: bool,
+    pub offline: bool,
+    pub cover_open: bool,
+    /// Paper is being fed with the paper feed button
+    pub feed_button_feeding: bool,
+    pub error: ErrorStatus,
+    pub paper: PaperStatus,
+}
+
+impl StatusBack {
+    /// Whether the byte can start a packet: bit 4 is set, bit 0, 1 and 7
+    /// are cleared
+    fn is_header(byte: u8) -> bool {
+        byte & 0b1001_0011 == 0b0001_0000
+    }
+
+    /// Whether the byte can follow the first one: bit 4 and 7 are cleared
+    fn is_body(byte: u8) -> bool {
+        byte & 0b1001_0000 == 0
+    }
+
+    pub fn from_bytes(bytes: [u8; 4]) -> Result<Self> {
+        if !Self::is_header(bytes[0]) {
+            return Err(Error::InvalidStatus(bytes[0]));
+        }
+        if let Some(byte) = bytes[1..].iter().find(|byte| !Self::is_body(**byte)) {
+            return Err(Error::InvalidStatus(*byte));
+        }
+        let [first, second, third, _] = bytes;
+        Ok(Self {
+            drawer_pin_high: bit(first, 2),
+            offline: bit(first, 3),
+            cover_open: bit(first, 5),
+            feed_button_feeding: bit(first, 6),
+            error: ErrorStatus {
+                recoverable: bit(second, 2),
+                autocutter: bit(second, 3),
+                unrecoverable: bit(second, 5),
+                auto_recoverable: bit(second, 6),
+            },
+            paper: PaperStatus {
+                near_end: bit(third, 0) || bit(third, 1),
+                end: bit(third, 2) || bit(third, 3),
+            },
+        })
+    }
+
+    fn has_error(&self) -> bool {
+        self.error != ErrorStatus::default()
+    }
+}
+
+/// A change between two Automatic Status Back packets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusEvent {
+    /// The level of pin 3 of the drawer kick-out connector changed
+    DrawerPin(bool),
+    Offline,
+    Online,
+    CoverOpened,
+    CoverClosed,
+    FeedButtonPressed,
+    FeedButtonReleased,
+    /// An error occurred or the kind of error changed
+    Error(ErrorStatus),
+    RecoveredFromError,
+    PaperNearEnd,
+    PaperEnd,
+    PaperLoaded,
+}
+
+impl StatusEvent {
+    fn diff(prev: &StatusBack, next: &StatusBack) -> Vec<Self> {
+        let mut events = Vec::new();
+        let mut changed = |prev: bool, next: bool, set: Self, cleared: Self| {
+            if prev != next {
+                events.push(if next { set } else { cleared });
+            }
+        };
+        changed(
+            prev.drawer_pin_high,
+            next.drawer_pin_high,
+            Self::DrawerPin(true),
+            Self::DrawerPin(false),
+        );
+        changed(prev.offline, next.offline, Self::Offline, Self::Online);
+        changed(
+            prev.cover_open,
+            next.cover_open,
+            Self::CoverOpened,
+            Self::CoverClosed,
+        );
+        changed(
+            prev.feed_button_feeding,
+            next.feed_button_feeding,
+            Self::FeedButtonPressed,
+            Self::FeedButtonReleased,
+        );
+        if prev.error != next.error {
+            events.push(if next.has_error() {
+                Self::Error(next.error)
+            } else {
+                Self::RecoveredFromError
+            });
+        }
+        if prev.paper != next.paper {
+            events.push(if next.paper.end {
+                Self::PaperEnd
+            } else if next.paper.near_end {
+                Self::PaperNearEnd
+            } else {
+                Self::PaperLoaded
+            });
+        }
+        events
+    }
+}
+
+/// Reads Automatic Status Back packets from the printer
+///
+/// As an iterator it blocks until the next status change and yields it as
+/// an event. The first packet is compared to a printer without any error,
+/// so conditions present when monitoring starts are reported as well.
+/// Automatic Status Back is disabled again when the monitor is dropped.
+pub struct StatusMonitor<'a, D>
+where
+    D: BidirectionalDevice,
+{
+    printer: &'a mut Printer<D>,
+    buffer: Vec<u8>,
+    status: Option<StatusBack>,
+    events: VecDeque<StatusEvent>,
+}
+
+impl<'a, D> StatusMonitor<'a, D>
+where
+    D: BidirectionalDevice,
+{
+    pub fn printer(&self) -> &Printer<D> {
+        self.printer
+    }
+
+    /// The monitored printer, e.g. to print while monitoring. Bytes it
+    /// sends in reply are skipped unless they form a status packet.
+    pub fn printer_mut(&mut self) -> &mut Printer<D> {
+        self.printer
+    }
+
+    /// The last reported status
+    pub fn status(&self) -> Option<StatusBack> {
+        self.status
+    }
+
+    /// Removes a complete packet from the buffer, skipping bytes which do
+    /// not belong to one
+    fn take_packet(&mut self) -> Option<[u8; 4]> {
+        loop {
+            let start = self
+                .buffer
+                .iter()
+                .position(|byte| StatusBack::is_header(*byte));
+            self.buffer.drain(..start.unwrap_or(self.buffer.len()));
+            if self.buffer.len() < 4 {
+                return None;
+            }
+            if self.buffer[1..4]
+                .iter()
+                .all(|byte| StatusBack::is_body(*byte))
+            {
+                let packet = [
+                    self.buffer[0],
+                    self.buffer[1],
+                    self.buffer[2],
+                    self.buffer[3],
+                ];
+                self.buffer.drain(..4);
+                return Some(packet);
+            }
+            self.buffer.remove(0);
+        }
+    }
+
+    /// Waits at most `timeout` for the next packet, returns `None` if none
+    /// arrived in time
+    pub fn read_status(&mut self, timeout: Duration) -> Result<Option<StatusBack>> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(packet) = self.take_packet() {
+                let status = StatusBack::from_bytes(packet)?;
+                let prev = self.status.unwrap_or_default();
+                self.events.extend(StatusEvent::diff(&prev, &status));
+                self.status = Some(status);
+                return Ok(Some(status));
+            }
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            let mut bytes = [0; 4];
+            let len = if timeout.is_zero() {
+                0
+            } else {
+                self.printer.device.read_timeout(&mut bytes, timeout)?
+            };
+            if len == 0 {
+                return Ok(None);
+            }
+            self.buffer.extend_from_slice(&bytes[..len]);
+        }
+    }
+}
+
+impl<'a, D> Iterator for StatusMonitor<'a, D>
+where
+    D: BidirectionalDevice,
+{
+    type Item = Result<StatusEvent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(event) = self.events.pop_front() {
+                return Some(Ok(event));
+            }
+            let timeout = self.printer.config.status_timeout;
+            if let Err(err) = self.read_status(timeout) {
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+impl<'a, D> Drop for StatusMonitor<'a, D>
+where
+    D: BidirectionalDevice,
+{
+    fn drop(&mut self) {
+        let _ = self.printer.auto_status_back(StatusBackMask::default());
+    }
+}
+
+impl<D> Printer<D>
+where
+    D: BidirectionalDevice,
+{
+    /// Sends DLE EOT and returns the status byte of the reply
+    pub fn transmit_status(&mut self, request: StatusRequest) -> Result<u8> {
+        self.command(&Command::TransmitStatus(request))?;
+        let deadline = Instant::now() + self.config.status_timeout;
+        let mut byte = [0];
+        loop {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            if timeout.is_zero() || self.device.read_timeout(&mut byte, timeout)? == 0 {
+                return Err(Error::StatusTimeout);
+            }
+            // skip other data the printer sent, e.g. automatic status back
+            if let Ok(byte) = check(byte[0]) {
+                return Ok(byte);
+            }
+        }
+    }
+
+    pub fn printer_status(&mut self) -> Result<PrinterStatus> {
+        PrinterStatus::from_byte(self.transmit_status(StatusRequest::Printer)?)
+    }
+
+    pub fn offline_status(&mut self) -> Result<OfflineStatus> {
+        OfflineStatus::from_byte(self.transmit_status(StatusRequest::Offline)?)
+    }
+
+    pub fn error_status(&mut self) -> Result<ErrorStatus> {
+        ErrorStatus::from_byte(self.transmit_status(StatusRequest::Error)?)
+    }
+
+    pub fn paper_status(&mut self) -> Result<PaperStatus> {
+        PaperStatus::from_byte(self.transmit_status(StatusRequest::Paper)?)
+    }
+
+    /// Requests all status bytes
+    pub fn status(&mut self) -> Result<Status> {
+        Ok(Status {
+            printer: self.printer_status()?,
+            offline: self.offline_status()?,
+            error: self.error_status()?,
+            paper: self.paper_status()?,
+        })
+    }
+
+    /// Enables Automatic Status Back for the changes in `mask` and monitors
+    /// the reported status
+    pub fn status_monitor(&mut self, mask: StatusBackMask) -> Result<StatusMonitor<'_, D>> {
+        self.auto_status_back(mask)?;
+        Ok(StatusMonitor {
+            printer: self,
+            buffer: Vec::new(),
+            status: None,
+            events: VecDeque::new(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PrinterConfig;
+    use std::io;
+
+    struct MockDevice {
+        written: Vec<u8>,
+        replies: VecDeque<u8>,
+    }
+
+    impl io::Write for MockDevice {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.written.write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl BidirectionalDevice for MockDevice {
+        fn read_timeout(&mut self, buf: &mut [u8], _: Duration) -> io::Result<usize> {
+            Ok(match self.replies.pop_front() {
+                Some(byte) => {
+                    buf[0] = byte;
+                    1
+                }
+                None => 0,
+            })
+        }
+    }
+
+    #[test]
+    fn status_replies() -> Result<()> {
+        let device = MockDevice {
+            written: Vec::new(),
+            // printer offline, cover open with paper end stop, no errors,
+            // an automatic status back byte and paper near end
+            replies: vec![0x1a, 0x36, 0x12, 0x10, 0x1e].into(),
+        };
+        let mut printer = PrinterConfig::default().build(device)?;
+        let status = printer.status()?;
+        assert_eq!(
+            printer.device().written,
+            b"\x10\x04\x01\x10\x04\x02\x10\x04\x03\x10\x04\x04"
+        );
+        assert!(status.printer.offline && !status.printer.feed_button_pressed);
+        assert!(status.offline.cover_open && status.offline.paper_end_stop);
+        assert_eq!(status.error, ErrorStatus::default());
+        assert!(status.paper.near_end && !status.paper.end);
+        assert!(!status.is_ready());
+        assert!(matches!(printer.paper_status(), Err(Error::StatusTimeout)));
+        Ok(())
+    }
+
+    #[test]
+    fn status_back_events() -> Result<()> {
+        let device = MockDevice {
+            written: Vec::new(),
+            replies: [
+                // paper near end
+                &[0x10, 0x00, 0x03, 0x00][..],
+                // a stray status byte, then cover open while offline
+                &[0x12, 0x38, 0x00, 0x03, 0x00],
+                // autocutter error with paper end
+                &[0x18, 0x08, 0x0f, 0x00],
+                // recovered with new paper
+                &[0x10, 0x00, 0x00, 0x00],
+            ]
+            .concat()
+            .into(),
+        };
+        let mut printer = PrinterConfig::default().build(device)?;
+        let mut monitor = printer.status_monitor(StatusBackMask::all())?;
+        let events = monitor.by_ref().take(7).collect::<Result<Vec<_>>>()?;
+        monitor.printer_mut().println("ok")?;
+        assert_eq!(monitor.printer().device().written, b"\x1d\x61\x0fok\n");
+        drop(monitor);
+        // disabled when the monitor is dropped
+        assert_eq!(printer.device().written, b"\x1d\x61\x0fok\n\x1d\x61\x00");
+        assert_eq!(
+            events,
+            vec![
+                StatusEvent::PaperNearEnd,
+                StatusEvent::Offline,
+                StatusEvent::CoverOpened,
+                StatusEvent::CoverClosed,
+                StatusEvent::Error(ErrorStatus {
+                    autocutter: true,
+                    ..Default::default()
+                }),
+                StatusEvent::PaperEnd,
+                StatusEvent::Online,
+            ]
+        );
+        Ok(())
+    }
+}
@@ -88,10 +88,7 @@ where
                 Op::Alphabet(b) => a == b,
                 _ => false,
             },
-            Op::Any => match other {
-                Op::Alphabet(_) => true,
-                _ => false,
-            },
+            Op::Any => matches!(other, Op::Alphabet(_)),
             Op::Begin => matches!(other, Op::Begin),
             Op::End => matches!(other, Op::End),
         }
@@ -383,7 +380,7 @@ impl Rule {
 
     fn ops<'a>(tree: &'a [StyleTag]) -> impl Iterator<Item = Op<StyleTag>> + 'a {
         std::iter::once(Op::Begin)
-            .chain(tree.iter().map(|tag| Op::Alphabet(tag.clone())))
+            .chain(tree.iter().map(|tag| Op::Alphabet(*tag)))
             .chain(Some(Op::End))
     }
 }
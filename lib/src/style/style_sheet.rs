@@ -338,6 +338,41 @@ impl Default for StyleSheet {
                     },
                 )
                 .unwrap();
+                this.push(
+                    "footnote",
+                    RelativeStyle {
+                        font: Some(Font::FontB),
+                        prefix: Some("[{num}] ".into()),
+                        margin_top: Some(12),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+                this.push(
+                    "footnote > p",
+                    RelativeStyle {
+                        display: Some(Display::Inline),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+                this.push(
+                    "footnoteref",
+                    RelativeStyle {
+                        display: Some(Display::Inline),
+                        font: Some(Font::FontB),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
+                this.push(
+                    "task",
+                    RelativeStyle {
+                        display: Some(Display::Inline),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
                 this.push(
                     "hr",
                     RelativeStyle {
@@ -346,6 +381,14 @@ impl Default for StyleSheet {
                     },
                 )
                 .unwrap();
+                this.push(
+                    "th",
+                    RelativeStyle {
+                        bold: Some(true),
+                        ..Default::default()
+                    },
+                )
+                .unwrap();
                 this.push(
                     "h1",
                     RelativeStyle {
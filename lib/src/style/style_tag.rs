@@ -25,6 +25,14 @@ pub enum StyleTag {
     A,
     Img,
     ImgCaption,
+    Table,
+    Thead,
+    Tr,
+    Th,
+    Td,
+    Footnote,
+    FootnoteRef,
+    Task,
 }
 
 impl FromStr for StyleTag {
@@ -52,6 +60,14 @@ impl FromStr for StyleTag {
             "a" => A,
             "img" => Img,
             "imgcaption" => ImgCaption,
+            "table" => Table,
+            "thead" => Thead,
+            "tr" => Tr,
+            "th" => Th,
+            "td" => Td,
+            "footnote" => Footnote,
+            "footnoteref" => FootnoteRef,
+            "task" => Task,
             _ => return Err(Error::InvalidRuleTag(s.to_string())),
         })
     }
@@ -81,6 +97,14 @@ impl Enumerable for StyleTag {
             A,
             Img,
             ImgCaption,
+            Table,
+            Thead,
+            Tr,
+            Th,
+            Td,
+            Footnote,
+            FootnoteRef,
+            Task,
         ]
     }
 }
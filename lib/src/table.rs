@@ -0,0 +1,321 @@
+use crate::error::{Error, Result};
+use crate::printer::{Printer, PrinterDevice};
+use crate::style::{Style, StyleSheet, StyleTag};
+use pulldown_cmark::Alignment;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TableBorder {
+    /// Columns separated by `│` with a `─┼─` rule under the header
+    #[default]
+    Box,
+    /// Columns separated by whitespace only
+    Whitespace,
+}
+
+impl TableBorder {
+    fn column_separator(&self) -> &'static str {
+        match self {
+            TableBorder::Box => " │ ",
+            TableBorder::Whitespace => "  ",
+        }
+    }
+
+    fn header_rule(&self) -> Option<(&'static str, &'static str)> {
+        match self {
+            TableBorder::Box => Some(("─", "─┼─")),
+            TableBorder::Whitespace => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TableOptions {
+    border: TableBorder,
+}
+
+impl TableOptions {
+    pub fn border(&mut self, border: TableBorder) -> &mut Self {
+        self.border = border;
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+struct TableRow {
+    head: bool,
+    cells: Vec<String>,
+}
+
+/// Contents of a markdown table collected until the whole table is known
+#[derive(Debug, Clone)]
+pub(crate) struct TableBuffer {
+    alignments: Vec<Alignment>,
+    rows: Vec<TableRow>,
+}
+
+impl TableBuffer {
+    pub fn new(alignments: Vec<Alignment>) -> Self {
+        Self {
+            alignments,
+            rows: Vec::new(),
+        }
+    }
+
+    pub fn start_row(&mut self, head: bool) {
+        self.rows.push(TableRow {
+            head,
+            cells: Vec::new(),
+        });
+    }
+
+    pub fn start_cell(&mut self) {
+        if let Some(row) = self.rows.last_mut() {
+            row.cells.push(String::new());
+        }
+    }
+
+    pub fn push_str(&mut self, text: &str) {
+        if let Some(cell) = self.rows.last_mut().and_then(|row| row.cells.last_mut()) {
+            cell.push_str(text);
+        }
+    }
+
+    fn num_columns(&self) -> usize {
+        self.rows
+            .iter()
+            .map(|row| row.cells.len())
+            .chain(Some(self.alignments.len()))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn alignment(&self, col: usize) -> Alignment {
+        self.alignments.get(col).cloned().unwrap_or(Alignment::None)
+    }
+}
+
+/// Distributes `available` dots among columns, shrinking the widest first.
+/// Columns stay at least as wide as the narrowest natural width if there
+/// is room for it in every column.
+fn fit_columns(natural: &[usize], available: usize) -> Vec<usize> {
+    if natural.iter().sum::<usize>() <= available {
+        return natural.to_vec();
+    }
+    let mut widths = vec![0; natural.len()];
+    let mut order = (0..natural.len()).collect::<Vec<_>>();
+    order.sort_by_key(|idx| natural[*idx]);
+    let mut remaining = available;
+    for (pos, idx) in order.iter().enumerate() {
+        let share = remaining / (order.len() - pos);
+        widths[*idx] = natural[*idx].min(share);
+        remaining -= widths[*idx];
+    }
+    widths
+}
+
+/// Wraps text on whitespace into lines of at most `width` chars, splitting
+/// words which are longer than a line
+fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut line_len = 0;
+    for word in text.split_whitespace() {
+        let mut word = word.chars().collect::<Vec<_>>();
+        if line_len != 0 && line_len + 1 + word.len() <= width {
+            line.push(' ');
+            line.extend(word.iter());
+            line_len += 1 + word.len();
+            continue;
+        }
+        if line_len != 0 {
+            lines.push(std::mem::take(&mut line));
+        }
+        while word.len() > width {
+            lines.push(word.drain(..width).collect());
+        }
+        line = word.iter().collect();
+        line_len = word.len();
+    }
+    if line_len != 0 || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+fn align_text(text: &str, width: usize, alignment: Alignment) -> String {
+    let padding = width.saturating_sub(text.chars().count());
+    let (left, right) = match alignment {
+        Alignment::None | Alignment::Left => (0, padding),
+        Alignment::Center => (padding / 2, padding - padding / 2),
+        Alignment::Right => (padding, 0),
+    };
+    format!("{}{}{}", " ".repeat(left), text, " ".repeat(right))
+}
+
+impl<D> Printer<D>
+where
+    D: PrinterDevice,
+{
+    fn char_size_for(&self, style: &Style) -> usize {
+        (self.config.font_widths.get(&style.font) + style.char_spacing)
+            * style.char_magnification.width() as usize
+    }
+
+    pub(crate) fn table(
+        &mut self,
+        table: &TableBuffer,
+        table_tags: &[StyleTag],
+        styles: &StyleSheet,
+        opts: &TableOptions,
+    ) -> Result<&mut Self> {
+        let num_columns = table.num_columns();
+        if num_columns == 0 {
+            return Ok(self);
+        }
+        // cells are padded by hand, so the printer must never wrap them
+        let get_style = |tags: &[StyleTag]| Style {
+            split_words: false,
+            ..styles.get(tags)
+        };
+        let table_style = get_style(table_tags);
+        let cell_style = |head: bool| {
+            let mut tags = table_tags.to_vec();
+            if head {
+                tags.extend_from_slice(&[StyleTag::Thead, StyleTag::Th]);
+            } else {
+                tags.extend_from_slice(&[StyleTag::Tr, StyleTag::Td]);
+            }
+            get_style(&tags)
+        };
+        let head_style = cell_style(true);
+        let body_style = cell_style(false);
+        let row_style = |row: &TableRow| {
+            if row.head {
+                &head_style
+            } else {
+                &body_style
+            }
+        };
+
+        let table_char_size = self.char_size_for(&table_style).max(1);
+        let separator = opts.border.column_separator();
+        let separator_width = separator.chars().count() * table_char_size;
+        // every column is at least one character wide in every row
+        let min_width = [&table_style, &head_style, &body_style]
+            .iter()
+            .map(|style| self.char_size_for(style).max(1))
+            .max()
+            .unwrap_or(table_char_size);
+
+        let mut natural = vec![min_width; num_columns];
+        for row in &table.rows {
+            let char_size = self.char_size_for(row_style(row));
+            for (col, cell) in row.cells.iter().enumerate() {
+                natural[col] = natural[col].max(cell.trim().chars().count() * char_size);
+            }
+        }
+        let available = self
+            .printable_width()
+            .saturating_sub(separator_width * (num_columns - 1));
+        if available < min_width * num_columns {
+            return Err(Error::TableTooWide(num_columns));
+        }
+        let widths = fit_columns(&natural, available);
+
+        for (row_idx, row) in table.rows.iter().enumerate() {
+            let style = row_style(row);
+            let char_size = self.char_size_for(style).max(1);
+            let cells = (0..num_columns)
+                .map(|col| {
+                    let text = row.cells.get(col).map(|cell| cell.trim()).unwrap_or("");
+                    wrap_text(text, widths[col] / char_size)
+                })
+                .collect::<Vec<_>>();
+            let num_lines = cells.iter().map(|lines| lines.len()).max().unwrap_or(1);
+
+            for line in 0..num_lines {
+                for (col, lines) in cells.iter().enumerate() {
+                    if col != 0 {
+                        self.font_style(&table_style)?.print(separator)?;
+                    }
+                    let text = lines.get(line).map(String::as_str).unwrap_or("");
+                    let mut aligned =
+                        align_text(text, widths[col] / char_size, table.alignment(col));
+                    if col == num_columns - 1 {
+                        aligned.truncate(aligned.trim_end().len());
+                    }
+                    self.font_style(style)?.print(aligned)?;
+                }
+                self.println("")?;
+            }
+
+            let next_is_body = table.rows.get(row_idx + 1).is_some_and(|next| !next.head);
+            if let (true, true, Some((bar, cross))) =
+                (row.head, next_is_body, opts.border.header_rule())
+            {
+                let rule = widths
+                    .iter()
+                    .map(|width| bar.repeat(width / table_char_size))
+                    .collect::<Vec<_>>()
+                    .join(cross);
+                self.font_style(&table_style)?.println(rule)?;
+            }
+        }
+
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::preview::TextPreview;
+    use crate::{MarkdownParser, MarkdownParserOptions, MarkdownRenderOptions, PrinterConfig};
+
+    #[test]
+    fn wrap() {
+        assert_eq!(wrap_text("", 4), vec![""]);
+        assert_eq!(wrap_text("ab cd ef", 5), vec!["ab cd", "ef"]);
+        assert_eq!(wrap_text("abcdefg h", 3), vec!["abc", "def", "g h"]);
+    }
+
+    #[test]
+    fn fit() {
+        assert_eq!(fit_columns(&[10, 20], 40), vec![10, 20]);
+        assert_eq!(fit_columns(&[10, 50, 40], 60), vec![10, 25, 25]);
+    }
+
+    #[test]
+    fn narrow_columns() -> Result<()> {
+        let render = |columns: usize| -> Result<Vec<String>> {
+            let row = |cell: &str| format!("|{}\n", format!(" {} |", cell).repeat(columns));
+            let md = format!("{}{}{}", row("Head"), row("---"), row("Long cell"));
+            let config = PrinterConfig::tm_t20ii();
+            let mut printer = config.clone().build(TextPreview::new(&config))?;
+            let parser = MarkdownParser::new_ext(&md, MarkdownParserOptions::ENABLE_TABLES);
+            printer.markdown(parser, &MarkdownRenderOptions::default())?;
+            Ok(printer
+                .device()
+                .render()
+                .lines()
+                .map(String::from)
+                .collect())
+        };
+        // 11 columns of one character and 10 separators fill the 41 characters
+        let lines = render(11)?;
+        assert!(lines
+            .iter()
+            .all(|line| line.trim_end().chars().count() <= 41));
+        assert!(lines.iter().any(|line| line.starts_with("H │ H │")));
+        assert!(matches!(render(12), Err(Error::TableTooWide(12))));
+        Ok(())
+    }
+
+    #[test]
+    fn align() {
+        assert_eq!(align_text("ab", 5, Alignment::Left), "ab   ");
+        assert_eq!(align_text("ab", 5, Alignment::Center), " ab  ");
+        assert_eq!(align_text("ab", 5, Alignment::Right), "   ab");
+    }
+}